@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use anyhow::{Error, bail, format_err};
 use linearize::Linearize;
@@ -108,6 +108,21 @@ pub enum Measure {
     Px(f64),
     Pt(f64),
     Pc(f64),
+    /// Relative to the available width
+    Percentage(f64),
+}
+
+impl FromStr for AlignH {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::AlignH as A;
+        Ok(match s {
+            "left" => A::Left,
+            "center" => A::Center,
+            "right" => A::Right,
+            s => bail!("Invalid Alignment {s}"),
+        })
+    }
 }
 
 impl FromStr for AlignHV {
@@ -142,13 +157,14 @@ impl FromStr for Measure {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use self::Measure as M;
-        let re =
-            Regex::new(r"(?P<float>\d+\.\d*|\.?\d+)\s*(?P<unit>em|ex|mm|cm|in|px|pt|pc)").unwrap();
+        // Like in docutils, a number without unit is in pixels
+        let re = Regex::new(r"(?P<float>\d+\.\d*|\.?\d+)\s*(?P<unit>em|ex|mm|cm|in|px|pt|pc|%)?")
+            .unwrap();
         let caps: regex::Captures = re
             .captures(s)
             .ok_or_else(|| format_err!("Invalid measure"))?;
         let value: f64 = caps["float"].parse()?;
-        Ok(match &caps["unit"] {
+        Ok(match caps.name("unit").map_or("px", |u| u.as_str()) {
             "em" => M::Em(value),
             "ex" => M::Ex(value),
             "mm" => M::Mm(value),
@@ -157,11 +173,30 @@ impl FromStr for Measure {
             "px" => M::Px(value),
             "pt" => M::Pt(value),
             "pc" => M::Pc(value),
+            "%" => M::Percentage(value),
             _ => unreachable!(),
         })
     }
 }
 
+/// Formats the measure as a CSS length.
+impl fmt::Display for Measure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::Measure as M;
+        match self {
+            M::Em(v) => write!(f, "{v}em"),
+            M::Ex(v) => write!(f, "{v}ex"),
+            M::Mm(v) => write!(f, "{v}mm"),
+            M::Cm(v) => write!(f, "{v}cm"),
+            M::In(v) => write!(f, "{v}in"),
+            M::Px(v) => write!(f, "{v}px"),
+            M::Pt(v) => write!(f, "{v}pt"),
+            M::Pc(v) => write!(f, "{v}pc"),
+            M::Percentage(v) => write!(f, "{v}%"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod parse_tests {
     use super::*;
@@ -172,6 +207,10 @@ mod parse_tests {
         let _b: Measure = "20 mm".parse().unwrap();
         let _c: Measure = ".5in".parse().unwrap();
         let _d: Measure = "1.pc".parse().unwrap();
+        let e: Measure = "50%".parse().unwrap();
+        assert_eq!(e.to_string(), "50%");
+        let f: Measure = "300".parse().unwrap();
+        assert_eq!(f, Measure::Px(300.0));
     }
 }
 
@@ -67,6 +67,19 @@ macro_rules! synonymous_enum {
             }
         }
 
+        impl AsElement for $name {
+            fn as_element(&self) -> Option<&dyn Element> {
+                match self {
+                    $( $name::$entry(inner) => inner.as_element(), )*
+                }
+            }
+            fn as_element_mut(&mut self) -> Option<&mut dyn Element> {
+                match self {
+                    $( $name::$entry(inner) => inner.as_element_mut(), )*
+                }
+            }
+        }
+
         $( impl From<$entry> for $name {
             fn from(inner: $entry) -> Self {
                 $name::$entry(Box::new(inner))
@@ -21,12 +21,31 @@ pub trait Element {
     /// An element may have at most one of the names or dupnames attributes, but not both.
     fn names(&self) -> &Vec<NameToken>;
     fn names_mut(&mut self) -> &mut Vec<NameToken>;
+    /// Names moved out of `names` because other elements have them too.
+    fn dupnames(&self) -> &Vec<NameToken>;
+    fn dupnames_mut(&mut self) -> &mut Vec<NameToken>;
     fn source(&self) -> &Option<PathBuf>;
     fn source_mut(&mut self) -> &mut Option<PathBuf>;
     fn classes(&self) -> &Vec<String>;
     fn classes_mut(&mut self) -> &mut Vec<String>;
 }
 
+/// Access to the [`Element`] wrapped in an element category.
+pub trait AsElement {
+    /// The element, or `None` for text.
+    fn as_element(&self) -> Option<&dyn Element>;
+    fn as_element_mut(&mut self) -> Option<&mut dyn Element>;
+}
+
+impl AsElement for String {
+    fn as_element(&self) -> Option<&dyn Element> {
+        None
+    }
+    fn as_element_mut(&mut self) -> Option<&mut dyn Element> {
+        None
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, JsonSchema)]
 pub struct CommonAttributes {
     #[serde(default, skip_serializing_if = "CanBeEmpty::is_empty")]
@@ -34,10 +53,11 @@ pub struct CommonAttributes {
     #[serde(default, skip_serializing_if = "CanBeEmpty::is_empty")]
     names: Vec<NameToken>,
     #[serde(default, skip_serializing_if = "CanBeEmpty::is_empty")]
+    dupnames: Vec<NameToken>,
+    #[serde(default, skip_serializing_if = "CanBeEmpty::is_empty")]
     source: Option<PathBuf>,
     #[serde(default, skip_serializing_if = "CanBeEmpty::is_empty")]
     classes: Vec<String>,
-    //TODO: dupnames
 }
 
 //----\\
@@ -59,6 +79,12 @@ macro_rules! impl_element {
             fn names_mut(&mut self) -> &mut Vec<NameToken> {
                 &mut self.common.names
             }
+            fn dupnames(&self) -> &Vec<NameToken> {
+                &self.common.dupnames
+            }
+            fn dupnames_mut(&mut self) -> &mut Vec<NameToken> {
+                &mut self.common.dupnames
+            }
             fn source(&self) -> &Option<PathBuf> {
                 &self.common.source
             }
@@ -72,6 +98,14 @@ macro_rules! impl_element {
                 &mut self.common.classes
             }
         }
+        impl AsElement for $name {
+            fn as_element(&self) -> Option<&dyn Element> {
+                Some(self)
+            }
+            fn as_element_mut(&mut self) -> Option<&mut dyn Element> {
+                Some(self)
+            }
+        }
     };
 }
 
@@ -224,7 +258,7 @@ impl_elems!(
     (Sidebar, SubSidebar)
 
     //structural subelements
-    (Title,      TextOrInlineElement)
+    (Title,      TextOrInlineElement; +)
     (Subtitle,   TextOrInlineElement)
     (Decoration, DecorationElement)
     (Docinfo,    BibliographicElement)
@@ -251,11 +285,11 @@ impl_elems!(
     (Paragraph,              TextOrInlineElement)
     (LiteralBlock,           TextOrInlineElement; +)
     (DoctestBlock,           TextOrInlineElement; +)
-    (MathBlock,              String)
+    (MathBlock,              String; +)
     (Rubric,                 TextOrInlineElement)
     (SubstitutionDefinition, TextOrInlineElement; +)
     (Comment,                TextOrInlineElement; +)
-    (Pending)
+    (Pending; +)
     (Target; +)
     (Raw, String; +)
     (Image; *)
@@ -31,6 +31,10 @@ macro_rules! impl_extra {
     );
 }
 
+impl_extra!(Title {
+    /// Backlink from a section title to its table of contents entry
+    refid: Option<ID>,
+});
 impl_extra!(Address { space: FixedSpace });
 impl_extra!(LiteralBlock { space: FixedSpace });
 impl_extra!(DoctestBlock { space: FixedSpace });
@@ -39,6 +43,12 @@ impl_extra!(SubstitutionDefinition {
     rtrim: bool
 });
 impl_extra!(Comment { space: FixedSpace });
+impl_extra!(Pending {
+    /// Name of the transform that resolves this element
+    transform: Option<NameToken>,
+    /// Data for the transform, e.g. directive options
+    details: Vec<(NameToken, String)>,
+});
 impl_extra!(Target {
     /// External reference to a URI/URL
     refuri: Option<Url>,
@@ -49,6 +59,10 @@ impl_extra!(Target {
     anonymous: bool,
 });
 impl_extra!(Raw { space: FixedSpace, format: Vec<NameToken> });
+impl_extra!(MathBlock {
+    /// Equation label. Labelled equations are numbered.
+    label: Option<NameToken>,
+});
 impl_extra!(#[derive(Clone, Debug, PartialEq, Serialize, JsonSchema)] Image {
     uri: Url,
     align: Option<AlignHV>,
@@ -63,12 +77,12 @@ impl_extra!(#[derive(Clone, Debug, PartialEq, Serialize, JsonSchema)] Image {
 //does auto actually mean the numbering prefix?
 
 impl_extra!(BulletList { bullet: Option<String> });
-impl_extra!(EnumeratedList { enumtype: Option<EnumeratedListType>, prefix: Option<String>, suffix: Option<String> });
+impl_extra!(EnumeratedList { enumtype: Option<EnumeratedListType>, prefix: Option<String>, suffix: Option<String>, start: Option<usize> });
 
 impl_extra!(Footnote { backrefs: Vec<ID>, auto: Option<FootnoteType> });
 impl_extra!(Citation { backrefs: Vec<ID> });
 impl_extra!(SystemMessage { backrefs: Vec<ID>, level: Option<usize>, line: Option<usize>, type_: Option<NameToken> });
-impl_extra!(Figure { align: Option<AlignH>, width: Option<usize> });
+impl_extra!(Figure { align: Option<AlignH>, width: Option<Measure> });
 impl_extra!(Table { frame: Option<TableBorder>, colsep: Option<bool>, rowsep: Option<bool>, pgwide: Option<bool> });
 
 impl_extra!(TableGroup { cols: TableGroupCols, colsep: Option<bool>, rowsep: Option<bool>, align: Option<TableAlignH> });
@@ -88,6 +102,11 @@ impl_extra!(Reference {
     refid: Option<ID>,
     /// Internal reference to the names attribute of another element
     refname: Vec<NameToken>,
+    /// Anonymous references are matched to anonymous targets in document order
+    anonymous: bool,
+    /// The reference as found in the source, e.g. to report it as problematic
+    #[serde(skip)]
+    rawsource: Option<String>,
 });
 impl_extra!(FootnoteReference { refid: Option<ID>, refname: Vec<NameToken>, auto: Option<FootnoteType> });
 impl_extra!(CitationReference { refid: Option<ID>, refname: Vec<NameToken> });
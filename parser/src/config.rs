@@ -0,0 +1,184 @@
+/*! Configuration of a parse.
+ *
+ * A [`Config`] holds the directive and role handlers available to documents
+ * and the [`IncludeLoader`] reading included files.
+ * It starts out with the built-in directives and roles and reads from the file system,
+ * and changes to it only apply to documents parsed with it:
+ *
+ * ```
+ * use document_tree::{HasChildren, elements as e};
+ * use rst_parser::{Config, directives::Directive};
+ *
+ * let mut config = Config::default();
+ * config.register_directive("shout", |d: &Directive| {
+ *     let text = d.arguments.to_uppercase();
+ *     Ok(vec![e::Paragraph::with_children(vec![text.into()]).into()])
+ * });
+ * let doc = config.parse(".. shout:: hello\n").unwrap();
+ * assert_eq!(doc.children().len(), 1);
+ * ```
+ */
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    path::Path,
+    sync::{Arc, LazyLock},
+};
+
+use anyhow::Error;
+use pest::Parser;
+
+use document_tree::Document;
+
+use crate::{
+    conversion::{convert_document, with_document_roles},
+    directives::{
+        DirectiveHandler, FileSystemLoader, IncludeLoader, builtin_directives, with_source_path,
+    },
+    pest_rst::{RstParser, Rule},
+    roles::{RoleHandler, builtin_roles},
+    transforms::standard_transform,
+};
+
+pub(crate) type Directives = HashMap<String, Arc<dyn DirectiveHandler>>;
+pub(crate) type Roles = HashMap<String, Arc<dyn RoleHandler>>;
+
+/// Handlers used while parsing a document.
+///
+/// Cloning is cheap, handlers are shared between clones.
+#[derive(Clone)]
+pub struct Config {
+    directives: Arc<Directives>,
+    roles: Arc<Roles>,
+    include_loader: Arc<dyn IncludeLoader>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            directives: Arc::new(builtin_directives()),
+            roles: Arc::new(builtin_roles()),
+            include_loader: Arc::new(FileSystemLoader),
+        }
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut directives: Vec<_> = self.directives.keys().collect();
+        directives.sort();
+        let mut roles: Vec<_> = self.roles.keys().collect();
+        roles.sort();
+        f.debug_struct("Config")
+            .field("directives", &directives)
+            .field("roles", &roles)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Used outside of a parse, e.g. when a handler is called directly.
+static DEFAULT: LazyLock<Config> = LazyLock::new(Config::default);
+
+thread_local! {
+    /// The configuration of the document currently being parsed.
+    static CURRENT: RefCell<Option<Config>> = const { RefCell::new(None) };
+}
+
+impl Config {
+    /// Registers a handler for the directive `name`, replacing any previous handler.
+    /// Directive names are case insensitive.
+    pub fn register_directive(
+        &mut self,
+        name: &str,
+        handler: impl DirectiveHandler + 'static,
+    ) -> &mut Self {
+        Arc::make_mut(&mut self.directives).insert(name.to_lowercase(), Arc::new(handler));
+        self
+    }
+
+    /// Returns whether a handler is registered for the directive `name`.
+    #[must_use]
+    pub fn has_directive(&self, name: &str) -> bool {
+        self.directives.contains_key(&name.to_lowercase())
+    }
+
+    /// Registers a handler for the role `name`, replacing any previous handler.
+    /// Role names are case insensitive.
+    pub fn register_role(&mut self, name: &str, handler: impl RoleHandler + 'static) -> &mut Self {
+        Arc::make_mut(&mut self.roles).insert(name.to_lowercase(), Arc::new(handler));
+        self
+    }
+
+    /// Returns whether a handler is registered for the role `name`.
+    #[must_use]
+    pub fn has_role(&self, name: &str) -> bool {
+        self.roles.contains_key(&name.to_lowercase())
+    }
+
+    /// Sets the loader used to read included files, replacing the previous one.
+    pub fn set_include_loader(&mut self, loader: impl IncludeLoader + 'static) -> &mut Self {
+        self.include_loader = Arc::new(loader);
+        self
+    }
+
+    /// Parse into a document tree and resolve sections, but not references.
+    ///
+    /// # Errors
+    /// Returns an error if parsing fails.
+    pub fn parse_only(&self, source: &str) -> Result<Document, Error> {
+        self.scope(|| {
+            let pairs = RstParser::parse(Rule::document, source)?;
+            with_document_roles(|| convert_document(pairs))
+        })
+    }
+
+    /// Parse into a document tree and resolve sections and references.
+    ///
+    /// # Errors
+    /// Returns an error if parsing fails.
+    pub fn parse(&self, source: &str) -> Result<Document, Error> {
+        self.parse_only(source).map(standard_transform)
+    }
+
+    /// Parse a document read from `path` into a document tree and resolve sections and references.
+    ///
+    /// Unlike [`Config::parse`], this resolves relative paths in include directives against the directory of `path`.
+    ///
+    /// # Errors
+    /// Returns an error if parsing fails.
+    pub fn parse_with_path(&self, source: &str, path: &Path) -> Result<Document, Error> {
+        with_source_path(path, || self.parse_only(source)).map(standard_transform)
+    }
+
+    pub(crate) fn directive(&self, name: &str) -> Option<Arc<dyn DirectiveHandler>> {
+        self.directives.get(name).cloned()
+    }
+
+    pub(crate) fn role(&self, name: &str) -> Option<Arc<dyn RoleHandler>> {
+        self.roles.get(name).cloned()
+    }
+
+    pub(crate) fn include_loader(&self) -> Arc<dyn IncludeLoader> {
+        self.include_loader.clone()
+    }
+
+    /// Runs `f` with this configuration as the current one.
+    fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        struct Guard(Option<Config>);
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                CURRENT.set(self.0.take());
+            }
+        }
+
+        let _guard = Guard(CURRENT.replace(Some(self.clone())));
+        f()
+    }
+}
+
+/// Calls `f` with the configuration of the document currently being parsed.
+pub(crate) fn with_current<T>(f: impl FnOnce(&Config) -> T) -> T {
+    CURRENT.with_borrow(|current| f(current.as_ref().unwrap_or(&DEFAULT)))
+}
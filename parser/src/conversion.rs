@@ -1,16 +1,22 @@
 mod block;
 mod inline;
+mod table;
 #[cfg(test)]
 mod tests;
 
 use anyhow::Error;
 use pest::iterators::Pairs;
+use unicode_normalization::UnicodeNormalization as _;
 
-use document_tree::{
-    Element, HasChildren, attribute_types as at, element_categories as c, elements as e,
-};
+use document_tree::{Element, HasChildren, element_categories as c, elements as e};
 
-use crate::pest_rst::Rule;
+pub(crate) use self::block::set_image_option;
+pub(crate) use self::inline::{define_role, set_default_role, with_document_roles};
+pub(crate) use self::table::{build_uniform_table, convert_cell_text};
+use crate::{
+    pest_rst::Rule,
+    report::{Level, system_message},
+};
 
 fn ssubel_to_section_unchecked_mut(ssubel: &mut c::StructuralSubElement) -> &mut e::Section {
     match ssubel {
@@ -46,31 +52,34 @@ pub fn convert_document(pairs: Pairs<Rule>) -> Result<e::Document, Error> {
     let mut section_idxs: Vec<Option<usize>> = vec![];
 
     for pair in pairs {
-        if let Some(ssubel) = block::convert_ssubel(pair)? {
+        for ssubel in block::convert_ssubel(pair)? {
             match ssubel {
-                Title(title, kind) => {
-                    match kinds.iter().position(|k| k == &kind) {
-                        // Idx points to the level we want to add,
-                        // so idx-1 needs to be the last valid index.
-                        Some(idx) => {
-                            // If idx < len: Remove found section and all below
-                            section_idxs.truncate(idx);
-                            // If idx > len: Add None for skipped levels
-                            // TODO: test skipped levels
-                            while section_idxs.len() < idx {
-                                section_idxs.push(None);
-                            }
-                        }
-                        None => kinds.push(kind),
+                Title(mut title, kind, line) => {
+                    // Idx points to the level we want to add,
+                    // so idx-1 needs to be the last valid index.
+                    // New kinds are one level below the deepest known one.
+                    let idx = kinds.iter().position(|k| k == &kind).unwrap_or_else(|| {
+                        kinds.push(kind);
+                        kinds.len() - 1
+                    });
+                    // If idx < len: Remove found section and all below
+                    section_idxs.truncate(idx);
+                    // If idx > len: Add None for skipped levels
+                    let inconsistent = section_idxs.len() < idx;
+                    while section_idxs.len() < idx {
+                        section_idxs.push(None);
                     }
                     let super_level = get_level(&mut toplevel, &section_idxs);
-                    let slug = title
-                        .names()
-                        .iter()
-                        .next()
-                        .map(|at::NameToken(name)| at::ID(name.to_owned()));
+                    if inconsistent {
+                        // Report the skipped level, but keep the section where it is.
+                        let msg =
+                            system_message(Level::Severe, "Title level inconsistent.", Some(line));
+                        super_level.push(c::BodyElement::from(msg).into());
+                    }
+                    // IDs are assigned by the standard transform
+                    let names = std::mem::take(title.names_mut());
                     let mut section = e::Section::with_children(vec![title.into()]);
-                    section.ids_mut().extend(slug.into_iter());
+                    section.names_mut().extend(names);
                     super_level.push(section.into());
                     section_idxs.push(Some(super_level.len() - 1));
                 }
@@ -81,6 +90,39 @@ pub fn convert_document(pairs: Pairs<Rule>) -> Result<e::Document, Error> {
     Ok(e::Document::with_children(toplevel))
 }
 
+/// Converts a nested document into body elements, e.g. the content of a directive.
+/// Section titles are not allowed there and are reported as errors.
+pub(crate) fn convert_body(pairs: Pairs<Rule>) -> Result<Vec<c::BodyElement>, Error> {
+    let mut body = vec![];
+    for pair in pairs.filter(|p| p.as_rule() != Rule::EOI) {
+        body.extend(block::convert_body_elems(pair)?);
+    }
+    Ok(body)
+}
+
+/// Converts a nested document that can contain topics and sidebars, but no sections.
+pub(crate) fn convert_structure(pairs: Pairs<Rule>) -> Result<Vec<c::SubStructure>, Error> {
+    let mut structure = vec![];
+    for pair in pairs.filter(|p| p.as_rule() != Rule::EOI) {
+        structure.extend(block::convert_substructure(pair)?);
+    }
+    Ok(structure)
+}
+
+/// Converts the content of a block quote directive, which can end with an attribution.
+pub(crate) fn convert_block_quote_body(pairs: Pairs<Rule>) -> Result<Vec<c::SubBlockQuote>, Error> {
+    block::convert_block_quote_inner(pairs.filter(|p| p.as_rule() != Rule::EOI))
+}
+
+/// Converts inline text outside of a paragraph, e.g. a title given as directive argument.
+/// Problems in it are returned as system messages, to be added after the element containing it.
+pub(crate) fn convert_inline_text(
+    pairs: Pairs<Rule>,
+) -> Result<(Vec<c::TextOrInlineElement>, Vec<e::SystemMessage>), Error> {
+    let inlines = inline::convert_inline_pairs(pairs.filter(|p| p.as_rule() != Rule::EOI))?;
+    Ok((inlines, inline::take_messages()))
+}
+
 /// Normalizes a name in terms of whitespace. Equivalent to docutils's
 /// `docutils.nodes.whitespace_normalize_name`.
 pub fn whitespace_normalize_name(name: &str) -> String {
@@ -97,3 +139,62 @@ pub fn whitespace_normalize_name(name: &str) -> String {
     }
     ret
 }
+
+/// Normalizes a name in terms of whitespace and case, as used for reference names.
+/// Equivalent to docutils’s `docutils.nodes.fully_normalize_name`.
+pub fn fully_normalize_name(name: &str) -> String {
+    whitespace_normalize_name(&name.to_lowercase())
+}
+
+/// Converts a name into a valid ID: ASCII letters, digits, and inner hyphens.
+/// Equivalent to docutils’s `docutils.nodes.make_id`.
+///
+/// The result can be empty, e.g. for names consisting of digits only.
+pub fn make_id(name: &str) -> String {
+    let mut ascii = String::new();
+    for ch in name.to_lowercase().chars() {
+        match ch {
+            // Characters that don’t decompose to ASCII
+            'ß' => ascii.push_str("sz"),
+            'æ' => ascii.push_str("ae"),
+            'œ' => ascii.push_str("oe"),
+            'ȸ' => ascii.push_str("db"),
+            'ȹ' => ascii.push_str("qp"),
+            'ø' => ascii.push('o'),
+            'đ' | 'ƌ' => ascii.push('d'),
+            'ħ' => ascii.push('h'),
+            'ı' => ascii.push('i'),
+            'ł' | 'ƚ' | 'ȴ' => ascii.push('l'),
+            'ŧ' | 'ƫ' | 'ƭ' | 'ȶ' => ascii.push('t'),
+            'ƀ' | 'ƃ' => ascii.push('b'),
+            'ƈ' | 'ȼ' => ascii.push('c'),
+            'ƒ' => ascii.push('f'),
+            'ƙ' => ascii.push('k'),
+            'ƞ' | 'ȵ' => ascii.push('n'),
+            'ƥ' => ascii.push('p'),
+            'ƴ' | 'ɏ' => ascii.push('y'),
+            'ƶ' | 'ȥ' | 'ɀ' => ascii.push('z'),
+            'ǥ' => ascii.push('g'),
+            'ȷ' | 'ɉ' => ascii.push('j'),
+            'ȿ' => ascii.push('s'),
+            'ɇ' => ascii.push('e'),
+            'ɋ' => ascii.push('q'),
+            'ɍ' => ascii.push('r'),
+            // Drop accents and other non-ASCII characters
+            ch => ascii.extend(std::iter::once(ch).nfkd().filter(char::is_ascii)),
+        }
+    }
+    let mut id = String::new();
+    for part in ascii.split(|ch: char| !ch.is_ascii_lowercase() && !ch.is_ascii_digit()) {
+        if part.is_empty() {
+            continue;
+        }
+        if !id.is_empty() {
+            id.push('-');
+        }
+        id.push_str(part);
+    }
+    // IDs have to start with a letter
+    id.trim_start_matches(|ch: char| ch == '-' || ch.is_ascii_digit())
+        .to_owned()
+}
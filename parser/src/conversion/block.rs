@@ -1,13 +1,30 @@
+use std::iter::once;
+
 use anyhow::{Error, bail};
-use pest::iterators::Pair;
+use pest::{
+    Parser,
+    iterators::{Pair, Pairs},
+};
+use unicode_width::UnicodeWidthStr as _;
 
 use document_tree::{
     Element, ExtraAttributes, HasChildren, attribute_types as at, element_categories as c,
     elements as e, extra_attributes as a,
 };
 
-use super::{inline::convert_inlines, whitespace_normalize_name};
-use crate::{pair_ext_parse::PairExt, pest_rst::Rule};
+use super::{
+    convert_body, fully_normalize_name,
+    inline::{convert_inlines, inlines_text, take_messages},
+    table::{convert_grid_table, convert_simple_table},
+    whitespace_normalize_name,
+};
+use crate::{
+    directives::{Directive, run_body_directive, run_directive},
+    pair_ext_parse::{PairExt, to_parse_error},
+    pest_rst::{RstParser, Rule},
+    report::{Level, system_message},
+    token::{EnumListChar, EnumListType},
+};
 
 #[derive(PartialEq)]
 pub(super) enum TitleKind {
@@ -16,84 +33,191 @@ pub(super) enum TitleKind {
 }
 
 pub(super) enum TitleOrSsubel {
-    Title(e::Title, TitleKind),
+    /// A section title with its adornment style and line number
+    Title(e::Title, TitleKind, usize),
     Ssubel(c::StructuralSubElement),
 }
 
-pub(super) fn convert_ssubel(pair: Pair<Rule>) -> Result<Option<TitleOrSsubel>, Error> {
-    use self::TitleOrSsubel::{Ssubel, Title};
-    Ok(Some(match pair.as_rule() {
+pub(super) fn convert_ssubel(pair: Pair<Rule>) -> Result<Vec<TitleOrSsubel>, Error> {
+    use self::TitleOrSsubel::Ssubel;
+    Ok(match pair.as_rule() {
         Rule::title => {
-            let (t, k) = convert_title(pair)?;
-            Title(t, k)
+            let mut elems = convert_title(pair)?;
+            elems.extend(
+                take_messages()
+                    .into_iter()
+                    .map(|msg| Ssubel(c::BodyElement::from(msg).into())),
+            );
+            elems
         }
-        //TODO: subtitle, decoration, docinfo
-        Rule::EOI => return Ok(None),
-        _ => Ssubel(convert_substructure(pair)?.into()),
-    }))
+        //TODO: subtitle, decoration (docinfo is extracted by a transform)
+        Rule::EOI => vec![],
+        _ => convert_substructure(pair)?
+            .into_iter()
+            .map(|s| Ssubel(s.into()))
+            .collect(),
+    })
 }
 
-fn convert_substructure(pair: Pair<Rule>) -> Result<c::SubStructure, Error> {
-    #[allow(clippy::match_single_binding)]
+pub(super) fn convert_substructure(pair: Pair<Rule>) -> Result<Vec<c::SubStructure>, Error> {
     Ok(match pair.as_rule() {
-        // TODO: Topic, Sidebar, Transition
+        Rule::transition => vec![e::Transition::default().into()],
+        Rule::directive => run_directive(&convert_directive(pair)),
         // no section here, as it’s constructed from titles
-        _ => convert_body_elem(pair)?.into(),
+        _ => convert_body_elems(pair)?
+            .into_iter()
+            .map(Into::into)
+            .collect(),
     })
 }
 
+/// Converts a block into body elements.
+/// Most blocks result in a single element, but e.g. an enumerated list
+/// is split into several lists if its items aren’t sequential.
+pub(crate) fn convert_body_elems(pair: Pair<Rule>) -> Result<Vec<c::BodyElement>, Error> {
+    let mut elems = match pair.as_rule() {
+        Rule::enumerated_list => convert_enumerated_lists(pair)?
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+        Rule::directive => run_body_directive(&convert_directive(pair)),
+        _ => vec![convert_body_elem(pair)?],
+    };
+    // Problems in inline markup are reported after the block containing it
+    elems.extend(take_messages().into_iter().map(Into::into));
+    Ok(elems)
+}
+
 fn convert_body_elem(pair: Pair<Rule>) -> Result<c::BodyElement, Error> {
     Ok(match pair.as_rule() {
         Rule::paragraph => convert_paragraph(pair)?.into(),
-        Rule::target => convert_target(pair)?.into(),
+        Rule::target | Rule::anonymous_target => convert_target(pair)?.into(),
         Rule::footnote => convert_footnote(pair)?.into(),
+        Rule::citation => convert_citation(pair)?.into(),
         Rule::substitution_def => convert_substitution_def(pair)?.into(),
-        Rule::block_quote_directive => convert_block_quote_directive(pair)?.into(),
-        Rule::admonition_gen => convert_admonition_gen(pair),
-        Rule::image => convert_image::<e::Image>(pair)?.into(),
+        Rule::field_list => convert_field_list(pair)?.into(),
+        Rule::option_list => convert_option_list(pair)?.into(),
+        Rule::line_block => convert_line_block(pair)?.into(),
         Rule::bullet_list => convert_bullet_list(pair)?.into(),
+        Rule::definition_list => convert_definition_list(pair)?.into(),
         Rule::block_quote => convert_block_quote(pair)?.into(),
         Rule::literal_block => convert_literal_block(pair).into(),
-        Rule::code_directive => convert_code_directive(pair).into(),
-        Rule::raw_directive => convert_raw_directive(pair).into(),
+        Rule::doctest_block => convert_doctest_block(pair).into(),
+        Rule::grid_table => convert_grid_table(pair)?,
+        Rule::simple_table => convert_simple_table(pair)?,
         Rule::block_comment => convert_comment(pair).into(),
+        // titles and transitions are only allowed at section or document level
+        Rule::title | Rule::transition => {
+            let line = pair.line_col().0;
+            let mut msg = system_message(
+                Level::Severe,
+                "Unexpected section title or transition.",
+                Some(line),
+            );
+            msg.append_child(e::LiteralBlock::with_children(vec![
+                pair.as_str().trim_end().into(),
+            ]));
+            msg.into()
+        }
         rule => unimplemented!("unhandled rule {:?}", rule),
     })
 }
 
-fn convert_title(pair: Pair<Rule>) -> Result<(e::Title, TitleKind), Error> {
-    let mut title: Option<String> = None;
-    let mut title_inlines: Option<Vec<c::TextOrInlineElement>> = None;
-    let mut adornment_char: Option<char> = None;
+/// Converts a section title, reporting problems with its adornments.
+/// A title with mismatching overline and underline is replaced by an error,
+/// one with a too short underline of less than 4 characters is treated as a paragraph.
+fn convert_title(pair: Pair<Rule>) -> Result<Vec<TitleOrSsubel>, Error> {
+    use self::TitleOrSsubel::{Ssubel, Title};
+
+    let source = pair.as_str();
+    let line = pair.line_col().0;
     // title_double or title_single. Extract kind before consuming
     let inner_pair = pair.into_inner().next().unwrap();
     let kind = inner_pair.as_rule();
+    let mut text: Option<Pair<Rule>> = None;
+    let mut adornments: Vec<Pair<Rule>> = vec![];
     for p in inner_pair.into_inner() {
         match p.as_rule() {
-            Rule::line => {
-                title = Some(p.as_str().to_owned());
-                title_inlines = Some(convert_inlines(p)?);
-            }
-            Rule::adornments => {
-                adornment_char = Some(p.as_str().chars().next().expect("Empty adornment?"));
-            }
+            Rule::line => text = Some(p),
+            Rule::adornments => adornments.push(p),
             rule => unimplemented!("Unexpected rule in title: {:?}", rule),
         }
     }
-    // now we encountered one line of text and one of adornments
-    // TODO: emit error if the adornment line is too short (has to match title length)
-    let mut elem = e::Title::with_children(title_inlines.expect("No text in title"));
-    if let Some(title) = title {
-        //TODO: slugify properly
-        let slug = title.to_lowercase().replace('\n', "").replace(' ', "-");
-        elem.names_mut().push(at::NameToken(slug));
-    }
-    let title_kind = match kind {
-        Rule::title_double => TitleKind::Double(adornment_char.unwrap()),
-        Rule::title_single => TitleKind::Single(adornment_char.unwrap()),
+    let text = text.expect("No text in title");
+    let title = text.as_str().trim_end().to_owned();
+    let width = title.width();
+    let mut messages: Vec<e::SystemMessage> = vec![];
+    let title_kind = match (kind, adornments.as_slice()) {
+        (Rule::title_double, [overline, underline]) => {
+            if overline.as_str() != underline.as_str() {
+                let mut msg = system_message(
+                    Level::Severe,
+                    "Title overline & underline mismatch.",
+                    Some(line),
+                );
+                msg.append_child(e::LiteralBlock::with_children(vec![
+                    source.trim_end().into(),
+                ]));
+                return Ok(vec![Ssubel(c::BodyElement::from(msg).into())]);
+            }
+            // the title text can be inset
+            let inset = text.line_col().1 - overline.line_col().1;
+            if inset + width > overline.as_str().len() {
+                messages.push(system_message(
+                    Level::Warning,
+                    "Title overline too short.",
+                    Some(line),
+                ));
+            }
+            TitleKind::Double(adornment_char(overline))
+        }
+        (Rule::title_single, [underline]) => {
+            let underline_len = underline.as_str().len();
+            if width > underline_len {
+                if underline_len < 4 {
+                    let msg = system_message(
+                        Level::Info,
+                        "Possible title underline, too short for the title.\n\
+                         Treating it as ordinary text because it’s so short.",
+                        Some(line + 1),
+                    );
+                    let mut inlines = convert_inlines(text)?;
+                    inlines.push(" ".into());
+                    inlines.push(underline.as_str().into());
+                    return Ok(vec![
+                        Ssubel(c::BodyElement::from(msg).into()),
+                        Ssubel(e::Paragraph::with_children(inlines).into()),
+                    ]);
+                }
+                messages.push(system_message(
+                    Level::Warning,
+                    "Title underline too short.",
+                    Some(line + 1),
+                ));
+            }
+            TitleKind::Single(adornment_char(underline))
+        }
         _ => unreachable!(),
     };
-    Ok((elem, title_kind))
+    let mut elem = e::Title::with_children(convert_inlines(text)?);
+    // Moved to the section, which is an implicit target
+    let name = fully_normalize_name(&inlines_text(elem.children()));
+    elem.names_mut().push(at::NameToken(name));
+    Ok(once(Title(elem, title_kind, line))
+        .chain(
+            messages
+                .into_iter()
+                .map(|m| Ssubel(c::BodyElement::from(m).into())),
+        )
+        .collect())
+}
+
+fn adornment_char(adornments: &Pair<Rule>) -> char {
+    adornments
+        .as_str()
+        .chars()
+        .next()
+        .expect("Empty adornment?")
 }
 
 fn convert_paragraph(pair: Pair<Rule>) -> Result<e::Paragraph, Error> {
@@ -102,15 +226,26 @@ fn convert_paragraph(pair: Pair<Rule>) -> Result<e::Paragraph, Error> {
 
 fn convert_target(pair: Pair<Rule>) -> Result<e::Target, Error> {
     let mut elem = e::Target::default();
-    elem.extra_mut().anonymous = false;
+    elem.extra_mut().anonymous = pair.as_rule() == Rule::anonymous_target;
     for p in pair.into_inner() {
         match p.as_rule() {
             Rule::target_name_uq | Rule::target_name_qu => {
-                elem.ids_mut().push(p.as_str().into());
-                elem.names_mut().push(p.as_str().into());
+                let name = fully_normalize_name(p.as_str());
+                elem.names_mut().push(at::NameToken(name));
             }
-            // TODO: also handle non-urls
-            Rule::link_target => elem.extra_mut().refuri = Some(p.parse()?),
+            Rule::link_target => match p.clone().into_inner().next() {
+                // Indirect target: `name_` or `` `name`_ ``
+                Some(name) => {
+                    let name = name.as_str().strip_suffix('_').unwrap();
+                    let name = name
+                        .strip_prefix('`')
+                        .and_then(|n| n.strip_suffix('`'))
+                        .unwrap_or(name);
+                    let name = fully_normalize_name(name);
+                    elem.extra_mut().refname.push(at::NameToken(name));
+                }
+                None => elem.extra_mut().refuri = Some(p.parse()?),
+            },
             rule => panic!("Unexpected rule in target: {rule:?}"),
         }
     }
@@ -127,7 +262,7 @@ fn convert_footnote(pair: Pair<Rule>) -> Result<e::Footnote, Error> {
     // turn `line` into paragraph
     children.push(convert_paragraph(pairs.next().unwrap())?.into());
     for p in pairs {
-        children.push(convert_body_elem(p)?.into());
+        children.extend(convert_body_elems(p)?.into_iter().map(Into::into));
     }
     let mut footnote = e::Footnote::with_children(children);
     footnote.extra_mut().auto = label.chars().next().unwrap().try_into().ok();
@@ -148,6 +283,24 @@ fn convert_footnote(pair: Pair<Rule>) -> Result<e::Footnote, Error> {
     Ok(footnote)
 }
 
+/// Converts a citation. Its name is the label in lowercase, as citation references are case insensitive.
+fn convert_citation(pair: Pair<Rule>) -> Result<e::Citation, Error> {
+    let mut pairs = pair.into_inner();
+    let label = pairs.next().unwrap().as_str();
+    let mut children: Vec<c::SubFootnote> =
+        vec![e::Label::with_children(vec![label.into()]).into()];
+    // turn `line` into paragraph
+    children.push(convert_paragraph(pairs.next().unwrap())?.into());
+    for p in pairs {
+        children.extend(convert_body_elems(p)?.into_iter().map(Into::into));
+    }
+    let mut citation = e::Citation::with_children(children);
+    citation
+        .names_mut()
+        .push(at::NameToken(label.to_lowercase()));
+    Ok(citation)
+}
+
 fn convert_substitution_def(pair: Pair<Rule>) -> Result<e::SubstitutionDefinition, Error> {
     let mut pairs = pair.into_inner();
     let name = whitespace_normalize_name(pairs.next().unwrap().as_str()); // Rule::substitution_name
@@ -180,101 +333,429 @@ where
         let mut opt_iter = opt.into_inner();
         let opt_name = opt_iter.next().unwrap();
         let opt_val = opt_iter.next().unwrap();
-        match opt_name.as_str() {
-            "class" => image.classes_mut().push(opt_val.as_str().to_owned()),
-            "name" => image.names_mut().push(opt_val.as_str().into()),
-            "alt" => image.extra_mut().alt = Some(opt_val.as_str().to_owned()),
-            "height" => image.extra_mut().height = Some(opt_val.parse()?),
-            "width" => image.extra_mut().width = Some(opt_val.parse()?),
-            "scale" => image.extra_mut().scale = Some(parse_scale(&opt_val)?),
-            "align" => image.extra_mut().align = Some(opt_val.parse()?),
-            "target" => image.extra_mut().target = Some(opt_val.parse()?),
-            name => bail!("Unknown Image option {name}"),
-        }
+        set_image_option(&mut image, opt_name.as_str(), opt_val.as_str().trim())
+            .map_err(|e| to_parse_error::<_, Rule>(opt_val.as_span(), &e))?;
     }
     Ok(image)
 }
 
-fn parse_scale(pair: &Pair<Rule>) -> Result<u8, Error> {
-    use pest::error::{Error, ErrorVariant};
+/// Sets an option of the image or figure directive on an image.
+pub(crate) fn set_image_option<I>(image: &mut I, name: &str, value: &str) -> Result<(), Error>
+where
+    I: Element + ExtraAttributes<a::Image>,
+{
+    match name {
+        "class" => image
+            .classes_mut()
+            .extend(value.split_whitespace().map(ToOwned::to_owned)),
+        "name" => image
+            .names_mut()
+            .push(at::NameToken(fully_normalize_name(value))),
+        "alt" => image.extra_mut().alt = Some(value.to_owned()),
+        "height" => image.extra_mut().height = Some(value.parse()?),
+        "width" => image.extra_mut().width = Some(value.parse()?),
+        "scale" => image.extra_mut().scale = Some(parse_scale(value)?),
+        "align" => image.extra_mut().align = Some(value.parse()?),
+        "target" => image.extra_mut().target = Some(value.parse()?),
+        name => bail!("Unknown Image option {name}"),
+    }
+    Ok(())
+}
 
-    let input = pair.as_str().trim();
+fn parse_scale(input: &str) -> Result<u8, std::num::ParseIntError> {
     let input = if let Some(percentage) = input.strip_suffix('%') {
         percentage.trim_end()
     } else {
         input
     };
-    Ok(input.parse().map_err(|e: std::num::ParseIntError| {
-        let var: ErrorVariant<Rule> = ErrorVariant::CustomError {
-            message: e.to_string(),
+    input.parse()
+}
+
+fn convert_field_list(pair: Pair<Rule>) -> Result<e::FieldList, Error> {
+    Ok(e::FieldList::with_children(
+        pair.into_inner()
+            .map(convert_field)
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+fn convert_field(pair: Pair<Rule>) -> Result<e::Field, Error> {
+    let mut pairs = pair.into_inner();
+    let name = pairs.next().unwrap(); // Rule::field_name
+    let mut body: Vec<c::BodyElement> = vec![];
+    for p in pairs {
+        match p.as_rule() {
+            Rule::line => body.push(convert_paragraph(p)?.into()),
+            Rule::field_body => {
+                for b in p.into_inner() {
+                    body.extend(convert_body_elems(b)?);
+                }
+            }
+            rule => unimplemented!("Unexpected rule in field: {:?}", rule),
+        }
+    }
+    Ok(e::Field::with_children(vec![
+        e::FieldName::with_children(vec![name.as_str().into()]).into(),
+        e::FieldBody::with_children(body).into(),
+    ]))
+}
+
+fn convert_option_list(pair: Pair<Rule>) -> Result<e::OptionList, Error> {
+    Ok(e::OptionList::with_children(
+        pair.into_inner()
+            .map(convert_option_list_item)
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+fn convert_option_list_item(pair: Pair<Rule>) -> Result<e::OptionListItem, Error> {
+    let mut pairs = pair.into_inner();
+    let group = pairs.next().unwrap(); // Rule::option_group
+    let description = pairs.next().unwrap(); // Rule::description
+    let options = group.into_inner().map(convert_option).collect();
+    let mut body: Vec<c::BodyElement> = vec![];
+    for p in description.into_inner() {
+        if p.as_rule() == Rule::description_line {
+            body.push(convert_paragraph(p)?.into());
+        } else {
+            body.extend(convert_body_elems(p)?);
+        }
+    }
+    Ok(e::OptionListItem::with_children(vec![
+        e::OptionGroup::with_children(options).into(),
+        e::Description::with_children(body).into(),
+    ]))
+}
+
+fn convert_option(pair: Pair<Rule>) -> e::Option_ {
+    let text = pair.as_str();
+    let mut pairs = pair.into_inner();
+    let string = pairs.next().unwrap().as_str(); // Rule::option_string
+    let mut children: Vec<c::SubOption> =
+        vec![e::OptionString::with_children(vec![string.to_owned()]).into()];
+    if let Some(arg) = pairs.next() {
+        let arg = arg.as_str();
+        // whatever is between the option string and the argument
+        let delimiter = &text[string.len()..text.len() - arg.len()];
+        let mut arg = e::OptionArgument::with_children(vec![arg.to_owned()]);
+        arg.extra_mut().delimiter = Some(delimiter.to_owned());
+        children.push(arg.into());
+    }
+    e::Option_::with_children(children)
+}
+
+fn convert_line_block(pair: Pair<Rule>) -> Result<e::LineBlock, Error> {
+    let mut lines: Vec<(usize, e::Line)> = vec![];
+    for line in pair.into_inner() {
+        let mut pairs = line.into_inner();
+        let (indent, line) = match (pairs.next(), pairs.next()) {
+            (Some(indent), Some(text)) => (
+                indent.as_str().len(),
+                e::Line::with_children(convert_inlines(text)?),
+            ),
+            // Empty lines have the indentation of the previous line
+            _ => (lines.last().map_or(0, |(i, _)| *i), e::Line::default()),
         };
-        Error::new_from_span(var, pair.as_span())
-    })?)
+        lines.push((indent, line));
+    }
+    Ok(e::LineBlock::with_children(nest_line_block_lines(lines)))
 }
 
-fn convert_admonition_gen(pair: Pair<Rule>) -> document_tree::element_categories::BodyElement {
-    let mut iter = pair.into_inner();
-    let typ = iter.next().unwrap().as_str();
-    // TODO: in reality it contains body elements.
-    let children: Vec<c::BodyElement> = iter
-        .map(|p| e::Paragraph::with_children(vec![p.as_str().into()]).into())
-        .collect();
-    match typ {
-        "attention" => e::Attention::with_children(children).into(),
-        "hint" => e::Hint::with_children(children).into(),
-        "note" => e::Note::with_children(children).into(),
-        "caution" => e::Caution::with_children(children).into(),
-        "danger" => e::Danger::with_children(children).into(),
-        "error" => e::Error::with_children(children).into(),
-        "important" => e::Important::with_children(children).into(),
-        "tip" => e::Tip::with_children(children).into(),
-        "warning" => e::Warning::with_children(children).into(),
-        typ => panic!("Unknown admontion type {typ}!"),
+/// Lines indented more than the least indented ones go into nested line blocks.
+/// Equivalent to docutils’ `nest_line_block_segment`.
+fn nest_line_block_lines(lines: Vec<(usize, e::Line)>) -> Vec<c::SubLineBlock> {
+    let least = lines.iter().map(|(i, _)| *i).min().unwrap_or(0);
+    let mut children: Vec<c::SubLineBlock> = vec![];
+    let mut nested: Vec<(usize, e::Line)> = vec![];
+    for (indent, line) in lines {
+        if indent > least {
+            nested.push((indent, line));
+            continue;
+        }
+        if !nested.is_empty() {
+            let block =
+                e::LineBlock::with_children(nest_line_block_lines(std::mem::take(&mut nested)));
+            children.push(block.into());
+        }
+        children.push(line.into());
+    }
+    if !nested.is_empty() {
+        children.push(e::LineBlock::with_children(nest_line_block_lines(nested)).into());
     }
+    children
 }
 
 fn convert_bullet_list(pair: Pair<Rule>) -> Result<e::BulletList, Error> {
     Ok(e::BulletList::with_children(
         pair.into_inner()
-            .map(convert_bullet_item)
+            .map(|item| {
+                if item.clone().into_inner().next().map(|p| p.as_rule())
+                    == Some(Rule::bullet_block_line)
+                {
+                    convert_block_list_item(&item)
+                } else {
+                    convert_list_item(item.into_inner())
+                }
+            })
             .collect::<Result<_, _>>()?,
     ))
 }
 
-fn convert_bullet_item(pair: Pair<Rule>) -> Result<e::ListItem, Error> {
-    let mut iter = pair.into_inner();
-    let mut children: Vec<c::BodyElement> = vec![convert_paragraph(iter.next().unwrap())?.into()];
-    for p in iter {
-        children.push(convert_body_elem(p)?);
+/// Converts a bullet list item starting with a block like a nested list
+/// by parsing its dedented text as a nested document.
+fn convert_block_list_item(item: &Pair<Rule>) -> Result<e::ListItem, Error> {
+    let outer = item.line_col().1 - 1;
+    let mut lines = item.as_str().lines();
+    let first = lines.next().unwrap_or_default();
+    let indent = first.len() - first[1..].trim_start().len();
+    let mut text = format!("{}\n", &first[indent..]);
+    for line in lines {
+        text.push_str(line.get(outer + indent..).unwrap_or_default());
+        text.push('\n');
+    }
+    let pairs = RstParser::parse(Rule::document, &text)?;
+    Ok(e::ListItem::with_children(convert_body(pairs)?))
+}
+
+/// Converts enumerated list items, starting a new list whenever an item
+/// doesn’t continue the previous one (like docutils does):
+/// The enumerator format and sequence have to match,
+/// and explicit ordinals have to be consecutive.
+fn convert_enumerated_lists(pair: Pair<Rule>) -> Result<Vec<e::EnumeratedList>, Error> {
+    let mut lists: Vec<e::EnumeratedList> = vec![];
+    let mut state: Option<EnumeratorState> = None;
+    for item in pair.into_inner() {
+        let mut pairs = item.into_inner();
+        let (typ, text) = split_enumerator(pairs.next().unwrap().as_str()); // Rule::enumerator
+        // Only the next enumerator in the same format continues a list
+        let expected = state
+            .as_ref()
+            .filter(|s| s.typ == typ && !s.auto)
+            .map(|s| (s.chr, s.ordinal + 1));
+        let (chr, ordinal) = parse_enumerator(text, expected)?;
+        let list_item = convert_list_item(pairs)?;
+
+        match state.as_mut() {
+            Some(s)
+                if typ == s.typ
+                    && (chr == EnumListChar::Auto
+                        || (!s.auto && chr == s.chr && ordinal == s.ordinal + 1)) =>
+            {
+                s.ordinal += 1;
+                s.auto |= chr == EnumListChar::Auto;
+                lists.last_mut().unwrap().append_child(list_item);
+            }
+            _ => {
+                let s = EnumeratorState {
+                    typ,
+                    // auto-enumerated lists are arabic
+                    chr: if chr == EnumListChar::Auto {
+                        EnumListChar::Arabic
+                    } else {
+                        chr
+                    },
+                    ordinal,
+                    auto: chr == EnumListChar::Auto,
+                };
+                lists.push(new_enumerated_list(&s, list_item));
+                state = Some(s);
+            }
+        }
+    }
+    Ok(lists)
+}
+
+/// The format, sequence, and last ordinal of the enumerated list being converted.
+struct EnumeratorState {
+    typ: EnumListType,
+    chr: EnumListChar,
+    ordinal: usize,
+    auto: bool,
+}
+
+fn new_enumerated_list(state: &EnumeratorState, first_item: e::ListItem) -> e::EnumeratedList {
+    let mut list = e::EnumeratedList::with_children(vec![first_item]);
+    let extra = list.extra_mut();
+    extra.enumtype = Some(match state.chr {
+        EnumListChar::Arabic | EnumListChar::Auto => at::EnumeratedListType::Arabic,
+        EnumListChar::AlphaLower => at::EnumeratedListType::LowerAlpha,
+        EnumListChar::AlphaUpper => at::EnumeratedListType::UpperAlpha,
+        EnumListChar::RomanLower => at::EnumeratedListType::LowerRoman,
+        EnumListChar::RomanUpper => at::EnumeratedListType::UpperRoman,
+    });
+    let (prefix, suffix) = match state.typ {
+        EnumListType::Period => ("", "."),
+        EnumListType::ParenEnclosed => ("(", ")"),
+        EnumListType::Paren => ("", ")"),
+    };
+    extra.prefix = Some(prefix.to_owned());
+    extra.suffix = Some(suffix.to_owned());
+    if state.ordinal != 1 {
+        extra.start = Some(state.ordinal);
+    }
+    list
+}
+
+/// Splits an enumerator like `(iv)` into its format and the enumerator text.
+fn split_enumerator(enumerator: &str) -> (EnumListType, &str) {
+    if let Some(text) = enumerator.strip_prefix('(') {
+        (EnumListType::ParenEnclosed, &text[..text.len() - 1])
+    } else if let Some(text) = enumerator.strip_suffix(')') {
+        (EnumListType::Paren, text)
+    } else {
+        (EnumListType::Period, &enumerator[..enumerator.len() - 1])
+    }
+}
+
+/// Determines an enumerator’s sequence and ordinal.
+/// Ambiguous enumerators like `i` or `C` are interpreted as part of the
+/// expected sequence if they are the expected next ordinal in it.
+/// Otherwise `i`/`I` are roman and other single letters alphabetic, like in docutils.
+fn parse_enumerator(
+    text: &str,
+    expected: Option<(EnumListChar, usize)>,
+) -> Result<(EnumListChar, usize), Error> {
+    use EnumListChar::{AlphaLower, AlphaUpper, Arabic, Auto, RomanLower, RomanUpper};
+
+    let chr = match (text, expected) {
+        ("#", _) => Auto,
+        (_, Some((chr, next))) if in_sequence(text, chr) && ordinal(text, chr)? == next => chr,
+        ("i", _) => RomanLower,
+        ("I", _) => RomanUpper,
+        _ => match [Arabic, AlphaLower, AlphaUpper, RomanLower, RomanUpper]
+            .into_iter()
+            .find(|&chr| in_sequence(text, chr))
+        {
+            Some(chr) => chr,
+            None => bail!("Invalid enumerator {text}"),
+        },
+    };
+    Ok((chr, ordinal(text, chr)?))
+}
+
+/// Gets the ordinal of an enumerator in a sequence it is part of.
+fn ordinal(text: &str, chr: EnumListChar) -> Result<usize, Error> {
+    use EnumListChar::{AlphaLower, AlphaUpper, Arabic, Auto, RomanLower, RomanUpper};
+
+    Ok(match chr {
+        Auto => 1,
+        Arabic => text.parse()?,
+        AlphaLower | AlphaUpper => usize::from(text.to_ascii_lowercase().as_bytes()[0] - b'a') + 1,
+        RomanLower | RomanUpper => roman_to_ordinal(text),
+    })
+}
+
+fn in_sequence(text: &str, chr: EnumListChar) -> bool {
+    let is_single = |f: fn(&char) -> bool| text.len() == 1 && text.chars().all(|c| f(&c));
+    match chr {
+        EnumListChar::Arabic => text.chars().all(|c| c.is_ascii_digit()),
+        EnumListChar::AlphaLower => is_single(char::is_ascii_lowercase),
+        EnumListChar::AlphaUpper => is_single(char::is_ascii_uppercase),
+        EnumListChar::RomanLower => text.chars().all(|c| "ivxlcdm".contains(c)),
+        EnumListChar::RomanUpper => text.chars().all(|c| "IVXLCDM".contains(c)),
+        EnumListChar::Auto => text == "#",
+    }
+}
+
+/// Converts a roman numeral. The grammar only allows valid ones.
+fn roman_to_ordinal(text: &str) -> usize {
+    let mut ordinal = 0;
+    let mut last = 0;
+    // Going from the right, smaller digits before larger ones are subtracted
+    for c in text.chars().rev() {
+        let value = match c.to_ascii_lowercase() {
+            'i' => 1,
+            'v' => 5,
+            'x' => 10,
+            'l' => 50,
+            'c' => 100,
+            'd' => 500,
+            'm' => 1000,
+            _ => unreachable!(),
+        };
+        if value < last {
+            ordinal -= value;
+        } else {
+            ordinal += value;
+            last = value;
+        }
+    }
+    ordinal
+}
+
+/// Converts a list item’s first line and body. The marker has already been consumed.
+fn convert_list_item(mut pairs: Pairs<Rule>) -> Result<e::ListItem, Error> {
+    let first = pairs.next().unwrap();
+    let first_line = first.line_col().0;
+    let mut paragraph = convert_paragraph(first)?;
+    let mut pairs = pairs.peekable();
+    // The first paragraph continues on the next lines if they directly follow
+    if let Some(p) =
+        pairs.next_if(|p| p.as_rule() == Rule::paragraph && p.line_col().0 == first_line + 1)
+    {
+        paragraph.append_child(" ");
+        paragraph.children_mut().extend(convert_inlines(p)?);
+    }
+    let mut children: Vec<c::BodyElement> = vec![paragraph.into()];
+    for p in pairs {
+        children.extend(convert_body_elems(p)?);
     }
     Ok(e::ListItem::with_children(children))
 }
 
-fn convert_block_quote(pair: Pair<Rule>) -> Result<e::BlockQuote, Error> {
-    Ok(e::BlockQuote::with_children(
+fn convert_definition_list(pair: Pair<Rule>) -> Result<e::DefinitionList, Error> {
+    Ok(e::DefinitionList::with_children(
         pair.into_inner()
-            .map(convert_block_quote_inner)
+            .map(convert_definition_list_item)
             .collect::<Result<_, _>>()?,
     ))
 }
 
-fn convert_block_quote_directive(pair: Pair<Rule>) -> Result<e::BlockQuote, Error> {
-    let mut iter = pair.into_inner();
-    let typ = iter.next().unwrap().as_str();
-    let children: Vec<c::SubBlockQuote> = iter
-        .map(convert_block_quote_inner)
-        .collect::<Result<_, _>>()?;
-    let mut bq = e::BlockQuote::with_children(children);
-    bq.classes_mut().push(typ.to_owned());
-    Ok(bq)
+fn convert_definition_list_item(pair: Pair<Rule>) -> Result<e::DefinitionListItem, Error> {
+    let mut children: Vec<c::SubDLItem> = vec![];
+    for p in pair.into_inner() {
+        children.push(match p.as_rule() {
+            Rule::term => e::Term::with_children(convert_inlines(p)?).into(),
+            Rule::classifier => e::Classifier::with_children(convert_inlines(p)?).into(),
+            Rule::definition => {
+                let mut body: Vec<c::BodyElement> = vec![];
+                for b in p.into_inner() {
+                    body.extend(convert_body_elems(b)?);
+                }
+                e::Definition::with_children(body).into()
+            }
+            rule => unimplemented!("Unexpected rule in definition list item: {:?}", rule),
+        });
+    }
+    Ok(e::DefinitionListItem::with_children(children))
 }
 
-fn convert_block_quote_inner(pair: Pair<Rule>) -> Result<c::SubBlockQuote, Error> {
-    Ok(if pair.as_rule() == Rule::attribution {
-        e::Attribution::with_children(convert_inlines(pair)?).into()
-    } else {
-        convert_body_elem(pair)?.into()
-    })
+fn convert_block_quote(pair: Pair<Rule>) -> Result<e::BlockQuote, Error> {
+    Ok(e::BlockQuote::with_children(convert_block_quote_inner(
+        pair.into_inner(),
+    )?))
+}
+
+pub(super) fn convert_block_quote_inner<'i>(
+    pairs: impl Iterator<Item = Pair<'i, Rule>>,
+) -> Result<Vec<c::SubBlockQuote>, Error> {
+    let mut children: Vec<c::SubBlockQuote> = vec![];
+    for pair in pairs {
+        if pair.as_rule() == Rule::attribution {
+            // The attribution can span several lines, which are joined like in paragraphs
+            let mut inlines = vec![];
+            for (i, line) in pair.into_inner().enumerate() {
+                if i > 0 {
+                    inlines.push(" ".to_owned().into());
+                }
+                inlines.extend(convert_inlines(line)?);
+            }
+            children.push(e::Attribution::with_children(inlines).into());
+        } else {
+            children.extend(convert_body_elems(pair)?.into_iter().map(Into::into));
+        }
+    }
+    Ok(children)
 }
 
 fn convert_literal_block(pair: Pair<Rule>) -> e::LiteralBlock {
@@ -296,41 +777,60 @@ fn convert_literal_lines(pair: Pair<Rule>) -> e::LiteralBlock {
     e::LiteralBlock::with_children(children)
 }
 
-fn convert_code_directive(pair: Pair<Rule>) -> e::LiteralBlock {
-    let mut iter = pair.into_inner();
-    let (lang, code) = match (iter.next().unwrap(), iter.next()) {
-        (lang, Some(code)) => (Some(lang), code),
-        (code, None) => (None, code),
+fn convert_doctest_block(pair: Pair<Rule>) -> e::DoctestBlock {
+    let lines = pair.into_inner().map(|l| l.as_str().into()).collect();
+    e::DoctestBlock::with_children(lines)
+}
+
+/// Splits a generic directive into arguments, options, and content.
+/// The lines before the first blank line are arguments or options,
+/// the ones after it are the content.
+fn convert_directive(pair: Pair<Rule>) -> Directive {
+    let mut directive = Directive {
+        line: pair.line_col().0,
+        source: pair.as_str().to_owned(),
+        ..Directive::default()
     };
-    let mut code_block = convert_literal_lines(code);
-    code_block.classes_mut().push("code".to_owned());
-    if let Some(lang) = lang {
-        code_block.classes_mut().push(lang.as_str().to_owned());
+    let mut arguments: Vec<&str> = vec![];
+    let mut in_content = false;
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::directive_name => directive.name = p.as_str().to_lowercase(),
+            Rule::directive_arg => arguments.push(p.as_str().trim_end()),
+            Rule::directive_line_blank if in_content => directive.content.push('\n'),
+            Rule::directive_line_blank => in_content = true,
+            Rule::directive_line if in_content => directive.content.push_str(p.as_str()),
+            Rule::directive_line => {
+                let line = p.as_str().trim_end();
+                if let Some(option) = split_directive_option(line) {
+                    directive.options.push(option);
+                } else if let Some((_, value)) = directive.options.last_mut() {
+                    // continuation of an option value
+                    value.push('\n');
+                    value.push_str(line.trim_start());
+                } else {
+                    arguments.push(line.trim_start());
+                }
+            }
+            rule => unreachable!("Unexpected rule in directive: {:?}", rule),
+        }
     }
-    code_block
+    directive.arguments = arguments.join("\n");
+    directive
 }
 
-fn convert_raw_directive(pair: Pair<Rule>) -> e::Raw {
-    let mut iter = pair.into_inner();
-    let format = iter.next().unwrap();
-    let block = iter.next().unwrap();
-    let children = block
-        .into_inner()
-        .map(|l| {
-            match l.as_rule() {
-                Rule::raw_line => l.as_str(),
-                Rule::raw_line_blank => "\n",
-                _ => unreachable!(),
-            }
-            .into()
-        })
-        .collect();
-    let mut raw_block = e::Raw::with_children(children);
-    raw_block
-        .extra_mut()
-        .format
-        .push(at::NameToken(format.as_str().to_owned()));
-    raw_block
+/// Splits an option line like `:name: value` into name and value.
+fn split_directive_option(line: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix(':')?;
+    let end = rest
+        .char_indices()
+        .find(|&(i, ch)| ch == ':' && rest[i + 1..].chars().next().is_none_or(|n| n == ' '))
+        .map(|(i, _)| i)?;
+    let name = &rest[..end];
+    if name.is_empty() || name.starts_with(' ') {
+        return None;
+    }
+    Some((name.to_lowercase(), rest[end + 1..].trim().to_owned()))
 }
 
 fn convert_comment(pair: Pair<Rule>) -> e::Comment {
@@ -1,4 +1,6 @@
-use anyhow::Error;
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::{Error, bail};
 use pest::iterators::Pair;
 
 use document_tree::{
@@ -8,12 +10,85 @@ use document_tree::{
     url::Url,
 };
 
-use super::whitespace_normalize_name;
-use crate::pest_rst::Rule;
+use super::{fully_normalize_name, whitespace_normalize_name};
+use crate::{
+    pest_rst::Rule,
+    report::{Level, system_message},
+    roles::{CustomRole, DEFAULT_ROLE, InterpretedText, is_known, run_role},
+};
+
+/// Roles defined by the `role` and `default-role` directives of the document being converted.
+#[derive(Debug, Default)]
+struct DocumentRoles {
+    custom: HashMap<String, CustomRole>,
+    default: Option<String>,
+}
+
+thread_local! {
+    /// Problems found in inline markup, reported after the block containing it.
+    static MESSAGES: RefCell<Vec<e::SystemMessage>> = const { RefCell::new(vec![]) };
+    static DOCUMENT_ROLES: RefCell<DocumentRoles> = RefCell::default();
+}
+
+/// Runs `f` to convert a document, with roles defined in it only applying until it ends.
+pub(crate) fn with_document_roles<T>(f: impl FnOnce() -> T) -> T {
+    struct Guard(DocumentRoles);
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            DOCUMENT_ROLES.set(std::mem::take(&mut self.0));
+        }
+    }
+
+    let _guard = Guard(DOCUMENT_ROLES.take());
+    f()
+}
+
+/// Defines a role for the rest of the document.
+/// Roles derived from another document role inherit its base and options.
+///
+/// # Errors
+/// Returns an error if the base role is unknown.
+pub(crate) fn define_role(name: &str, mut role: CustomRole) -> Result<(), Error> {
+    DOCUMENT_ROLES.with_borrow_mut(|roles| {
+        if let Some(base) = role.base.as_ref().and_then(|b| roles.custom.get(b)) {
+            role.options.splice(0..0, base.options.iter().cloned());
+            role.base.clone_from(&base.base);
+        } else if let Some(base) = role.base.as_ref().filter(|b| !is_known(b)) {
+            bail!("Unknown interpreted text role “{base}”");
+        }
+        roles.custom.insert(name.to_owned(), role);
+        Ok(())
+    })
+}
+
+/// Sets the role of interpreted text without explicit role for the rest of the document.
+/// `None` restores the standard default role.
+///
+/// # Errors
+/// Returns an error if the role is unknown.
+pub(crate) fn set_default_role(name: Option<&str>) -> Result<(), Error> {
+    DOCUMENT_ROLES.with_borrow_mut(|roles| {
+        if let Some(name) = name
+            && !is_known(name)
+            && !roles.custom.contains_key(name)
+        {
+            bail!("Unknown interpreted text role “{name}”");
+        }
+        roles.default = name.map(ToOwned::to_owned);
+        Ok(())
+    })
+}
+
+/// Returns the system messages for inline markup converted since the last call.
+pub(super) fn take_messages() -> Vec<e::SystemMessage> {
+    MESSAGES.with_borrow_mut(std::mem::take)
+}
 
 pub fn convert_inline(pair: Pair<Rule>) -> Result<c::TextOrInlineElement, Error> {
     Ok(match pair.as_rule() {
-        Rule::str | Rule::str_nested => pair.as_str().into(),
+        Rule::str | Rule::str_nested | Rule::term_str => pair.as_str().into(),
+        // Escaped whitespace is removed, e.g. to separate inline markup from text
+        Rule::escaped_char if pair.as_str() == "\\ " => String::new().into(),
         Rule::escaped_char => pair.as_str()[1..].into(),
         Rule::ws_newline => " ".to_owned().into(),
         Rule::reference => convert_reference(pair)?,
@@ -22,12 +97,61 @@ pub fn convert_inline(pair: Pair<Rule>) -> Result<c::TextOrInlineElement, Error>
         Rule::strong => e::Strong::with_children(convert_inlines(pair)?).into(),
         Rule::literal => e::Literal::with_children(vec![pair.as_str().to_owned()]).into(),
         Rule::footnote_reference => convert_footnote_reference(pair).into(),
+        Rule::citation_reference => convert_citation_reference(pair).into(),
         rule => unimplemented!("unknown rule {:?}", rule),
     })
 }
 
 pub fn convert_inlines(pair: Pair<Rule>) -> Result<Vec<c::TextOrInlineElement>, Error> {
-    pair.into_inner().map(convert_inline).collect()
+    convert_inline_pairs(pair.into_inner())
+}
+
+pub(super) fn convert_inline_pairs<'i>(
+    pairs: impl Iterator<Item = Pair<'i, Rule>>,
+) -> Result<Vec<c::TextOrInlineElement>, Error> {
+    let mut inlines = vec![];
+    for inner in pairs {
+        match inner.as_rule() {
+            // Roles can result in any number of elements
+            Rule::interpreted_text => inlines.extend(convert_interpreted_text(inner)),
+            _ => inlines.push(convert_inline(inner)?),
+        }
+    }
+    Ok(inlines)
+}
+
+fn convert_interpreted_text(pair: Pair<Rule>) -> Vec<c::TextOrInlineElement> {
+    let mut text = InterpretedText {
+        line: pair.line_col().0,
+        source: pair.as_str().to_owned(),
+        ..InterpretedText::default()
+    };
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::role_name => text.role = inner.as_str().to_lowercase(),
+            Rule::interpreted_content => inner.as_str().clone_into(&mut text.text),
+            _ => unreachable!(),
+        }
+    }
+    let custom = DOCUMENT_ROLES.with_borrow(|roles| {
+        if text.role.is_empty() {
+            roles
+                .default
+                .as_deref()
+                .unwrap_or(DEFAULT_ROLE)
+                .clone_into(&mut text.role);
+        }
+        roles.custom.get(&text.role).cloned()
+    });
+    let result = match custom {
+        Some(custom) => custom.run(&text),
+        None => run_role(&text, &text.role),
+    };
+    result.unwrap_or_else(|message| {
+        let msg = system_message(Level::Error, &message, Some(text.line));
+        MESSAGES.with_borrow_mut(|messages| messages.push(msg));
+        vec![e::Problematic::with_children(vec![text.source.into()]).into()]
+    })
 }
 
 fn convert_reference(pair: Pair<Rule>) -> Result<c::TextOrInlineElement, Error> {
@@ -41,7 +165,11 @@ fn convert_reference(pair: Pair<Rule>) -> Result<c::TextOrInlineElement, Error>
 }
 
 fn convert_reference_target(concrete: Pair<'_, Rule>) -> Result<e::Reference, Error> {
-    let rt_inner = concrete.into_inner().next().unwrap();
+    let rawsource = Some(concrete.as_str().to_owned());
+    let mut pairs = concrete.into_inner();
+    let rt_inner = pairs.next().unwrap();
+    let anonymous = pairs.next().map(|p| p.as_rule()) == Some(Rule::reference_anonymous);
+    // Anonymous references are resolved by position instead of by name
     Ok(match rt_inner.as_rule() {
         Rule::reference_target_uq => e::Reference::new(
             CommonAttributes::default(),
@@ -49,7 +177,13 @@ fn convert_reference_target(concrete: Pair<'_, Rule>) -> Result<e::Reference, Er
                 name: Some(rt_inner.as_str().into()),
                 refuri: None,
                 refid: None,
-                refname: vec![rt_inner.as_str().into()],
+                refname: if anonymous {
+                    vec![]
+                } else {
+                    vec![at::NameToken(fully_normalize_name(rt_inner.as_str()))]
+                },
+                anonymous,
+                rawsource,
             },
             vec![rt_inner.as_str().into()],
         ),
@@ -74,6 +208,8 @@ fn convert_reference_target(concrete: Pair<'_, Rule>) -> Result<e::Reference, Er
                     .unwrap_or_else(|| reference.clone().into_inner().next().unwrap().as_str()),
                 (None, None) => unreachable!(),
             };
+            // Anonymous references with an embedded URI or alias are already resolved
+            let anonymous = anonymous && reference.is_none();
             let (refuri, refname): (Option<Url>, Vec<at::NameToken>) =
                 if let Some(reference) = reference {
                     let inner = reference.into_inner().next().unwrap();
@@ -86,20 +222,29 @@ fn convert_reference_target(concrete: Pair<'_, Rule>) -> Result<e::Reference, Er
                             } else if inner.as_str().ends_with('_') {
                                 // like target_name_qu (minus the final underscore)
                                 let full_str = inner.as_str();
-                                (None, vec![full_str[0..full_str.len() - 1].into()])
+                                let name = fully_normalize_name(&full_str[0..full_str.len() - 1]);
+                                (None, vec![at::NameToken(name)])
                             } else {
                                 // like relative_reference
                                 (Some(Url::parse_relative(inner.as_str())?), Vec::new())
                             }
                         }
-                        Rule::target_name_qu => (None, vec![inner.as_str().into()]),
+                        Rule::target_name_qu => (
+                            None,
+                            vec![at::NameToken(fully_normalize_name(inner.as_str()))],
+                        ),
                         Rule::relative_reference => {
                             (Some(Url::parse_relative(inner.as_str())?), Vec::new())
                         }
                         _ => unreachable!(),
                     }
+                } else if anonymous {
+                    (None, Vec::new())
                 } else {
-                    (None, vec![trimmed_text.into()])
+                    (
+                        None,
+                        vec![at::NameToken(fully_normalize_name(trimmed_text))],
+                    )
                 };
             e::Reference::new(
                 CommonAttributes::default(),
@@ -108,6 +253,8 @@ fn convert_reference_target(concrete: Pair<'_, Rule>) -> Result<e::Reference, Er
                     refuri,
                     refid: None,
                     refname,
+                    anonymous,
+                    rawsource,
                 },
                 vec![trimmed_text.into()],
             )
@@ -134,6 +281,8 @@ fn convert_reference_auto(concrete: Pair<'_, Rule>) -> c::TextOrInlineElement {
             refuri: Some(target),
             refid: None,
             refname: Vec::new(),
+            anonymous: false,
+            rawsource: None,
         },
         vec![str],
     )
@@ -161,3 +310,42 @@ fn convert_footnote_reference(pair: Pair<Rule>) -> e::FootnoteReference {
     }
     fr
 }
+
+fn convert_citation_reference(pair: Pair<Rule>) -> e::CitationReference {
+    let label = pair.into_inner().next().unwrap().as_str();
+    let mut cr = e::CitationReference::with_children(vec![label.into()]);
+    cr.extra_mut()
+        .refname
+        .push(at::NameToken(label.to_lowercase()));
+    cr
+}
+
+/// Returns the text of inline elements without markup, like docutils’s `Node.astext()`.
+pub(super) fn inlines_text(inlines: &[c::TextOrInlineElement]) -> String {
+    use c::TextOrInlineElement as T;
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            T::String(text) => text.as_str().to_owned(),
+            T::Emphasis(e) => inlines_text(e.children()),
+            T::Strong(e) => inlines_text(e.children()),
+            T::Reference(e) => inlines_text(e.children()),
+            T::FootnoteReference(e) => inlines_text(e.children()),
+            T::CitationReference(e) => inlines_text(e.children()),
+            T::SubstitutionReference(e) => inlines_text(e.children()),
+            T::TitleReference(e) => inlines_text(e.children()),
+            T::Abbreviation(e) => inlines_text(e.children()),
+            T::Acronym(e) => inlines_text(e.children()),
+            T::Superscript(e) => inlines_text(e.children()),
+            T::Subscript(e) => inlines_text(e.children()),
+            T::Inline(e) => inlines_text(e.children()),
+            T::Problematic(e) => inlines_text(e.children()),
+            T::Generated(e) => inlines_text(e.children()),
+            T::Literal(e) => e.children().concat(),
+            T::Math(e) => e.children().concat(),
+            T::TargetInline(e) => e.children().concat(),
+            T::RawInline(e) => e.children().concat(),
+            T::ImageInline(_) => String::new(),
+        })
+        .collect()
+}
@@ -0,0 +1,643 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Error, bail};
+use pest::{Parser, iterators::Pair};
+
+use document_tree::{
+    ExtraAttributes, HasChildren, attribute_types as at, element_categories as c, elements as e,
+    extra_attributes as a,
+};
+
+use super::block::convert_body_elems;
+use crate::{
+    pest_rst::{RstParser, Rule},
+    report::{Level, system_message},
+};
+
+/// A cell in a table: its position in row and column units,
+/// how many rows and columns it spans, and its text.
+struct Cell {
+    row: usize,
+    col: usize,
+    morerows: usize,
+    morecols: usize,
+    text: String,
+}
+
+/// The structure of a table independent of its markup.
+struct TableStructure {
+    colwidths: Vec<usize>,
+    n_head_rows: usize,
+    n_rows: usize,
+    cells: Vec<Cell>,
+}
+
+/// Converts a grid table. A malformed table is reported with its source instead.
+pub(super) fn convert_grid_table(pair: Pair<Rule>) -> Result<c::BodyElement, Error> {
+    let (line, source) = (pair.line_col().0, pair.as_str());
+    let lines: Vec<Vec<char>> = pair
+        .into_inner()
+        .map(|l| l.as_str().trim_end().chars().collect())
+        .collect();
+    match GridTableParser::new(lines).and_then(GridTableParser::parse) {
+        Ok(structure) => Ok(build_table(structure)?.into()),
+        Err(err) => Ok(malformed_table(&err, line, source).into()),
+    }
+}
+
+/// Converts a simple table. A malformed table is reported with its source instead.
+pub(super) fn convert_simple_table(pair: Pair<Rule>) -> Result<c::BodyElement, Error> {
+    let (line, source) = (pair.line_col().0, pair.as_str());
+    let lines: Vec<Vec<char>> = pair
+        .into_inner()
+        .map(|l| match l.as_rule() {
+            Rule::simple_table_blank => vec![],
+            _ => l.as_str().trim_end().chars().collect(),
+        })
+        .collect();
+    match SimpleTableParser::new(lines).and_then(SimpleTableParser::parse) {
+        Ok(structure) => Ok(build_table(structure)?.into()),
+        Err(err) => Ok(malformed_table(&err, line, source).into()),
+    }
+}
+
+/// Creates the error reported instead of a malformed table, like docutils does.
+fn malformed_table(err: &Error, line: usize, source: &str) -> e::SystemMessage {
+    let mut msg = system_message(
+        Level::Error,
+        &format!("Malformed table.\n{err}"),
+        Some(line),
+    );
+    msg.append_child(e::LiteralBlock::with_children(vec![
+        source.trim_end().into(),
+    ]));
+    msg
+}
+
+/// Builds the table element hierarchy from a parsed table structure.
+fn build_table(structure: TableStructure) -> Result<e::Table, Error> {
+    let TableStructure {
+        colwidths,
+        n_head_rows,
+        n_rows,
+        mut cells,
+    } = structure;
+    cells.sort_by_key(|cell| (cell.row, cell.col));
+
+    let mut rows: Vec<e::TableRow> = (0..n_rows).map(|_| e::TableRow::default()).collect();
+    for cell in cells {
+        let mut entry = e::TableEntry::with_children(convert_cell_text(&cell.text)?);
+        entry.extra_mut().morerows = (cell.morerows > 0).then_some(cell.morerows);
+        entry.extra_mut().morecols = (cell.morecols > 0).then_some(cell.morecols);
+        rows[cell.row].append_child(entry);
+    }
+    let colwidths = colwidths.into_iter().map(Some).collect();
+    Ok(build_table_group(colwidths, rows, n_head_rows, 0))
+}
+
+/// Builds a table with one cell per row and column, e.g. from a list or CSV table.
+/// Without widths, the columns are sized automatically.
+pub(crate) fn build_uniform_table(
+    rows: Vec<Vec<Vec<c::BodyElement>>>,
+    colwidths: Option<Vec<usize>>,
+    n_head_rows: usize,
+    n_stub_columns: usize,
+) -> e::Table {
+    let n_cols = rows.first().map_or(0, Vec::len);
+    let colwidths = match colwidths {
+        Some(widths) => widths.into_iter().map(Some).collect(),
+        None => vec![None; n_cols],
+    };
+    let rows = rows
+        .into_iter()
+        .map(|cells| {
+            e::TableRow::with_children(
+                cells
+                    .into_iter()
+                    .map(e::TableEntry::with_children)
+                    .collect(),
+            )
+        })
+        .collect();
+    build_table_group(colwidths, rows, n_head_rows, n_stub_columns)
+}
+
+fn build_table_group(
+    colwidths: Vec<Option<usize>>,
+    mut rows: Vec<e::TableRow>,
+    n_head_rows: usize,
+    n_stub_columns: usize,
+) -> e::Table {
+    let body_rows = rows.split_off(n_head_rows);
+
+    let mut group = e::TableGroup::with_extra(a::TableGroup {
+        cols: at::TableGroupCols(colwidths.len()),
+        ..Default::default()
+    });
+    for (i, width) in colwidths.into_iter().enumerate() {
+        group.append_child(e::TableColspec::with_extra(a::TableColspec {
+            colwidth: width.map(|w| w.to_string()),
+            stub: (i < n_stub_columns).then_some(true),
+            ..Default::default()
+        }));
+    }
+    if !rows.is_empty() {
+        group.append_child(e::TableHead::with_children(rows));
+    }
+    group.append_child(e::TableBody::with_children(body_rows));
+    e::Table::with_children(vec![group.into()])
+}
+
+/// Parses the text of a table cell into body elements.
+/// Section titles are reported as unexpected.
+pub(crate) fn convert_cell_text(text: &str) -> Result<Vec<c::BodyElement>, Error> {
+    if text.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let mut children = vec![];
+    for pair in RstParser::parse(Rule::document, text)? {
+        if pair.as_rule() != Rule::EOI {
+            children.extend(convert_body_elems(pair)?);
+        }
+    }
+    Ok(children)
+}
+
+/// Checks if a line only consists of whitespace in the given column range.
+fn is_blank(line: &[char], start: usize, end: usize) -> bool {
+    let end = end.min(line.len());
+    line[start.min(end)..end]
+        .iter()
+        .all(|ch| ch.is_whitespace())
+}
+
+/// Extracts the text of a rectangular block, removing common indentation and trailing blank lines.
+fn extract_block(
+    lines: &[Vec<char>],
+    top: usize,
+    left: usize,
+    bottom: usize,
+    right: usize,
+) -> String {
+    let block: Vec<String> = lines[top..bottom]
+        .iter()
+        .map(|line| {
+            let right = right.min(line.len());
+            line[left.min(right)..right]
+                .iter()
+                .collect::<String>()
+                .trim_end()
+                .to_owned()
+        })
+        .collect();
+    let indent = block
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut text = String::new();
+    for line in block {
+        text.push_str(line.get(indent..).unwrap_or(""));
+        text.push('\n');
+    }
+    // Trailing blank lines are not part of the cell’s content
+    text.truncate(text.trim_end().len());
+    text.push('\n');
+    text
+}
+
+/// The result of scanning a cell’s borders.
+struct CellScan {
+    bottom: usize,
+    right: usize,
+    rowseps: Vec<usize>,
+    colseps: Vec<usize>,
+}
+
+/// Parses a grid table into cells. A port of docutils’ `GridTableParser`.
+///
+/// Starting at the top left corner, each cell is scanned clockwise.
+/// The bottom left and top right corners of each cell found are then used as starting points for the next cells.
+struct GridTableParser {
+    lines: Vec<Vec<char>>,
+    bottom: usize,
+    right: usize,
+    head_body_sep: Option<usize>,
+    /// For each column, the last line already covered by a cell.
+    done: Vec<Option<usize>>,
+    rowseps: BTreeSet<usize>,
+    colseps: BTreeSet<usize>,
+    /// Cells as (top, left, bottom, right) coordinates.
+    cells: Vec<(usize, usize, usize, usize)>,
+}
+
+impl GridTableParser {
+    fn new(mut lines: Vec<Vec<char>>) -> Result<Self, Error> {
+        let width = lines[0].len();
+        if lines
+            .iter()
+            .any(|line| line.len() != width || !matches!(line.last(), Some('+' | '|')))
+        {
+            bail!("Lines differ in length or are not terminated by a border.");
+        }
+        let mut head_body_sep = None;
+        for (i, line) in lines.iter_mut().enumerate() {
+            if line.len() > 2
+                && line[0] == '+'
+                && line[1] == '='
+                && line[width - 2] == '='
+                && line[width - 1] == '+'
+                && line.iter().all(|&ch| ch == '=' || ch == '+')
+            {
+                if let Some(sep) = head_body_sep {
+                    bail!(
+                        "Multiple head/body row separators (table lines {} and {}); only one allowed.",
+                        sep + 1,
+                        i + 1
+                    );
+                }
+                head_body_sep = Some(i);
+                for ch in line.iter_mut().filter(|ch| **ch == '=') {
+                    *ch = '-';
+                }
+            }
+        }
+        let bottom = lines.len() - 1;
+        if matches!(head_body_sep, Some(sep) if sep == bottom) {
+            bail!("No body rows after the head/body row separator.");
+        }
+        Ok(Self {
+            lines,
+            bottom,
+            right: width - 1,
+            head_body_sep,
+            done: vec![None; width],
+            rowseps: BTreeSet::from([0]),
+            colseps: BTreeSet::from([0]),
+            cells: vec![],
+        })
+    }
+
+    fn parse(mut self) -> Result<TableStructure, Error> {
+        let mut corners = vec![(0, 0)];
+        while !corners.is_empty() {
+            let (top, left) = corners.remove(0);
+            if top == self.bottom
+                || left == self.right
+                || self.done[left].is_some_and(|done| top <= done)
+            {
+                continue;
+            }
+            let Some(CellScan {
+                bottom,
+                right,
+                rowseps,
+                colseps,
+            }) = self.scan_cell(top, left)
+            else {
+                continue;
+            };
+            self.rowseps.extend(rowseps);
+            self.colseps.extend(colseps);
+            self.mark_done(top, left, bottom, right);
+            self.cells.push((top, left, bottom, right));
+            corners.extend([(top, right), (bottom, left)]);
+            corners.sort_unstable();
+        }
+        if self.done[..self.right]
+            .iter()
+            .any(|&done| done != Some(self.bottom - 1))
+        {
+            bail!("Parse incomplete.");
+        }
+        Ok(self.structure())
+    }
+
+    fn mark_done(&mut self, top: usize, left: usize, bottom: usize, right: usize) {
+        for done in &mut self.done[left..right] {
+            debug_assert_eq!(*done, top.checked_sub(1));
+            *done = Some(bottom - 1);
+        }
+    }
+
+    /// Scans a cell clockwise, returning its bottom right corner and the row and column separators along its borders.
+    fn scan_cell(&self, top: usize, left: usize) -> Option<CellScan> {
+        if self.lines[top][left] != '+' {
+            return None;
+        }
+        self.scan_right(top, left)
+    }
+
+    fn scan_right(&self, top: usize, left: usize) -> Option<CellScan> {
+        let mut colseps = vec![];
+        for i in left + 1..=self.right {
+            match self.lines[top][i] {
+                '+' => {
+                    colseps.push(i);
+                    if let Some(mut scan) = self.scan_down(top, left, i) {
+                        scan.colseps.extend(colseps);
+                        return Some(scan);
+                    }
+                }
+                '-' => {}
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn scan_down(&self, top: usize, left: usize, right: usize) -> Option<CellScan> {
+        let mut rowseps = vec![];
+        for i in top + 1..=self.bottom {
+            match self.lines[i][right] {
+                '+' => {
+                    rowseps.push(i);
+                    if let Some(mut scan) = self.scan_left(top, left, i, right) {
+                        scan.rowseps.extend(rowseps);
+                        return Some(scan);
+                    }
+                }
+                '|' => {}
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn scan_left(&self, top: usize, left: usize, bottom: usize, right: usize) -> Option<CellScan> {
+        let line = &self.lines[bottom];
+        let mut colseps = vec![];
+        for i in (left + 1..right).rev() {
+            match line[i] {
+                '+' => colseps.push(i),
+                '-' => {}
+                _ => return None,
+            }
+        }
+        if line[left] != '+' {
+            return None;
+        }
+        let rowseps = self.scan_up(top, left, bottom)?;
+        Some(CellScan {
+            bottom,
+            right,
+            rowseps,
+            colseps,
+        })
+    }
+
+    fn scan_up(&self, top: usize, left: usize, bottom: usize) -> Option<Vec<usize>> {
+        let mut rowseps = vec![];
+        for i in (top + 1..bottom).rev() {
+            match self.lines[i][left] {
+                '+' => rowseps.push(i),
+                '|' => {}
+                _ => return None,
+            }
+        }
+        Some(rowseps)
+    }
+
+    fn structure(self) -> TableStructure {
+        let rowseps: Vec<usize> = self.rowseps.iter().copied().collect();
+        let colseps: Vec<usize> = self.colseps.iter().copied().collect();
+        let row_index = |line: usize| rowseps.binary_search(&line).unwrap();
+        let col_index = |col: usize| colseps.binary_search(&col).unwrap();
+        let cells = self
+            .cells
+            .iter()
+            .map(|&(top, left, bottom, right)| {
+                let (row, col) = (row_index(top), col_index(left));
+                Cell {
+                    row,
+                    col,
+                    morerows: row_index(bottom) - row - 1,
+                    morecols: col_index(right) - col - 1,
+                    text: extract_block(&self.lines, top + 1, left + 1, bottom, right),
+                }
+            })
+            .collect();
+        TableStructure {
+            colwidths: colseps.windows(2).map(|w| w[1] - w[0] - 1).collect(),
+            n_head_rows: self.head_body_sep.map_or(0, row_index),
+            n_rows: rowseps.len() - 1,
+            cells,
+        }
+    }
+}
+
+/// Parses a simple table into cells. A port of docutils’ `SimpleTableParser`.
+///
+/// The top border defines the columns.
+/// A row starts with text in the first column, so rows with a blank first column continue the previous row.
+/// Column span underlines (made of `-`) and borders end a row and define which columns its cells span.
+struct SimpleTableParser {
+    lines: Vec<Vec<char>>,
+    head_body_sep: Option<usize>,
+    /// Columns as (start, end) offsets. The last column can grow to fit overflowing text.
+    columns: Vec<(usize, usize)>,
+    /// End of the top border’s last column.
+    border_end: usize,
+    /// Rows as (first line, cells).
+    rows: Vec<(usize, Vec<Cell>)>,
+}
+
+impl SimpleTableParser {
+    fn new(mut lines: Vec<Vec<char>>) -> Result<Self, Error> {
+        // Convert top and bottom borders to column span underlines
+        let last = lines.len() - 1;
+        for i in [0, last] {
+            for ch in lines[i].iter_mut().filter(|ch| **ch == '=') {
+                *ch = '-';
+            }
+        }
+        let mut head_body_sep = None;
+        for (i, line) in lines.iter_mut().enumerate() {
+            if line.first() == Some(&'=') && line.iter().all(|&ch| ch == '=' || ch == ' ') {
+                if let Some(sep) = head_body_sep {
+                    bail!(
+                        "Multiple head/body row separators (table lines {} and {}); only one allowed.",
+                        sep + 1,
+                        i + 1
+                    );
+                }
+                head_body_sep = Some(i);
+                for ch in line.iter_mut().filter(|ch| **ch == '=') {
+                    *ch = '-';
+                }
+            }
+        }
+        let columns = column_spans(&lines[0]);
+        let border_end = columns.last().map_or(0, |&(_, end)| end);
+        Ok(Self {
+            lines,
+            head_body_sep,
+            columns,
+            border_end,
+            rows: vec![],
+        })
+    }
+
+    fn parse(mut self) -> Result<TableStructure, Error> {
+        let (first_start, first_end) = self.columns[0];
+        let mut start = 1;
+        let mut text_found = false;
+        for offset in 1..self.lines.len() {
+            let line = &self.lines[offset];
+            if is_span_line(line) {
+                // Column span underline or border: the row is complete
+                self.parse_row(start, offset, Some(offset))?;
+                start = offset + 1;
+                text_found = false;
+            } else if !is_blank(line, first_start, first_end) {
+                // First column not blank: a new row starts
+                if text_found && offset != start {
+                    self.parse_row(start, offset, None)?;
+                }
+                start = offset;
+                text_found = true;
+            } else if !text_found {
+                start = offset + 1;
+            }
+        }
+        Ok(self.structure())
+    }
+
+    /// Parses the row spanning `lines[start..end]`, with an optional column span underline.
+    fn parse_row(
+        &mut self,
+        start: usize,
+        end: usize,
+        spanline: Option<usize>,
+    ) -> Result<(), Error> {
+        let mut columns = if let Some(spanline) = spanline {
+            self.span_columns(spanline)?
+        } else if start == end {
+            // No new row, just blank lines
+            return Ok(());
+        } else {
+            self.columns.clone()
+        };
+        self.check_columns(start, end, &mut columns)?;
+
+        let row = self.rows.len();
+        let mut cells = vec![];
+        let mut i = 0;
+        for (col_start, col_end) in columns {
+            let col = i;
+            if self.columns.get(i).map(|&(s, _)| s) != Some(col_start) {
+                bail!("Column span alignment problem in table line {}.", end + 1);
+            }
+            while self.columns[i].1 != col_end {
+                i += 1;
+                if i == self.columns.len() {
+                    bail!("Column span alignment problem in table line {}.", end + 1);
+                }
+            }
+            cells.push(Cell {
+                row,
+                col,
+                morerows: 0,
+                morecols: i - col,
+                text: extract_block(&self.lines, start, col_start, end, col_end),
+            });
+            i += 1;
+        }
+        self.rows.push((start, cells));
+        Ok(())
+    }
+
+    /// Gets the columns defined by a column span underline.
+    fn span_columns(&self, offset: usize) -> Result<Vec<(usize, usize)>, Error> {
+        let mut columns = column_spans(&self.lines[offset]);
+        let Some(last) = columns.last_mut() else {
+            bail!("Empty column span underline in table line {}.", offset + 1);
+        };
+        if last.1 != self.border_end {
+            bail!("Column span incomplete in table line {}.", offset + 1);
+        }
+        // Allow for an unbounded rightmost column
+        last.1 = self.columns.last().unwrap().1;
+        Ok(columns)
+    }
+
+    /// Checks for text in column margins, and extends the last column if text overflows it.
+    fn check_columns(
+        &mut self,
+        start: usize,
+        end: usize,
+        columns: &mut [(usize, usize)],
+    ) -> Result<(), Error> {
+        let last_col = columns.len() - 1;
+        for i in 0..columns.len() {
+            let col_end = columns[i].1;
+            let next_start = columns.get(i + 1).map_or(usize::MAX, |&(s, _)| s);
+            for (offset, line) in self.lines[start..end].iter().enumerate() {
+                if i == last_col && !is_blank(line, col_end, usize::MAX) {
+                    let text_end = line.len()
+                        - line
+                            .iter()
+                            .rev()
+                            .take_while(|ch| ch.is_whitespace())
+                            .count();
+                    let main_end = self.columns.last().unwrap().1;
+                    columns[i].1 = main_end.max(text_end);
+                    if text_end > main_end {
+                        self.columns.last_mut().unwrap().1 = text_end;
+                    }
+                } else if !is_blank(line, col_end, next_start) {
+                    bail!(
+                        "Text in column margin in table line {}.",
+                        start + offset + 1
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn structure(self) -> TableStructure {
+        let n_head_rows = self.head_body_sep.map_or(0, |sep| {
+            self.rows
+                .iter()
+                .take_while(|(first_line, _)| *first_line < sep)
+                .count()
+        });
+        TableStructure {
+            colwidths: self
+                .columns
+                .iter()
+                .map(|(start, end)| end - start)
+                .collect(),
+            n_head_rows,
+            n_rows: self.rows.len(),
+            cells: self.rows.into_iter().flat_map(|(_, cells)| cells).collect(),
+        }
+    }
+}
+
+/// Checks if a line is a column span underline (or a converted border).
+fn is_span_line(line: &[char]) -> bool {
+    line.first() == Some(&'-') && line.iter().all(|&ch| ch == '-' || ch == ' ')
+}
+
+/// Gets the (start, end) offsets of runs of non-space characters in a border or underline.
+fn column_spans(line: &[char]) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    let mut start = None;
+    for (i, &ch) in line.iter().enumerate() {
+        match (ch == ' ', start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, line.len()));
+    }
+    spans
+}
@@ -1,8 +1,12 @@
+use std::path::{Path, PathBuf};
+
 use document_tree::{
-    HasChildren, element_categories as c, elements as e, extra_attributes::ExtraAttributes,
+    Element, HasChildren, attribute_types as at, element_categories as c, elements as e,
+    extra_attributes::ExtraAttributes,
 };
 
-use crate::parse;
+use super::make_id;
+use crate::{Config, directives::Directive, parse, roles::InterpretedText};
 
 fn ssubel_to_section(ssubel: &c::StructuralSubElement) -> &e::Section {
     match ssubel {
@@ -93,11 +97,16 @@ fn convert_skipped_section() {
     let lvl1_b = ssubel_to_section(&lvl0[2]).children();
     assert_eq!(
         lvl1_b.len(),
-        2,
-        "The 2nd lvl1 section should have (a title and) a single lvl2 section as child: {lvl1_b:?}"
+        3,
+        "The 2nd lvl1 section should have (a title,) an error and a lvl3 section as child: {lvl1_b:?}"
     );
     //TODO: test title lvl1b[0]
-    let lvl3_b = ssubel_to_section(&lvl1_b[1]).children();
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl1_b[1]) else {
+        panic!("Expected an error for the skipped level: {lvl1_b:?}");
+    };
+    assert_eq!(msg.extra().level, Some(4));
+    assert_eq!(msg.extra().line, Some(16));
+    let lvl3_b = ssubel_to_section(&lvl1_b[2]).children();
     assert_eq!(
         lvl3_b.len(),
         1,
@@ -106,6 +115,39 @@ fn convert_skipped_section() {
     //TODO: test title lvl3b[0]
 }
 
+const NEW_TITLE_STYLE: &str = "\
+A
+=
+
+B
+-
+
+C
+=
+
+D
+~
+";
+
+/// A new title style below a known deeper one skips a level.
+#[test]
+fn convert_new_title_style_inconsistent() {
+    let doctree = parse(NEW_TITLE_STYLE).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "Should be 2 sections: {lvl0:?}");
+    let c = ssubel_to_section(&lvl0[1]).children();
+    assert_eq!(
+        c.len(),
+        3,
+        "Expected a title, an error and a section: {c:?}"
+    );
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&c[1]) else {
+        panic!("Expected an error for the skipped level: {c:?}");
+    };
+    assert_eq!((msg.extra().level, msg.extra().line), (Some(4), Some(10)));
+    ssubel_to_section(&c[2]);
+}
+
 #[test]
 fn test_convert_image_scale() {
     let doctree = parse(".. image:: /path/to/img.jpg\n   :scale: 90%\n\n").unwrap();
@@ -116,3 +158,1607 @@ fn test_convert_image_scale() {
     assert_eq!(img.extra().scale, Some(90));
     assert_eq!(img.extra().uri, "/path/to/img.jpg".parse().unwrap());
 }
+
+fn body_element_to_table(bodyel: &c::BodyElement) -> &e::Table {
+    match bodyel {
+        c::BodyElement::Table(t) => t,
+        c => panic!("Expected Table, not {c:?}"),
+    }
+}
+
+const GRID_TABLE: &str = "\
++------------+-----+
+| Header     | Two |
++============+=====+
+| Spans both cols  |
++------------+-----+
+| Spans two  | x   |
+| rows       +-----+
+|            | * y |
++------------+-----+
+";
+
+#[test]
+fn convert_grid_table() {
+    let doctree = parse(GRID_TABLE).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 1, "Should be a single table: {lvl0:?}");
+    let table = body_element_to_table(ssubel_to_body_element(&lvl0[0]));
+    let [c::SubTable::TableGroup(group)] = table.children().as_slice() else {
+        panic!("Expected a single table group: {table:?}");
+    };
+    assert_eq!(group.extra().cols.0, 2);
+    let [
+        c::SubTableGroup::TableColspec(spec1),
+        c::SubTableGroup::TableColspec(spec2),
+        c::SubTableGroup::TableHead(head),
+        c::SubTableGroup::TableBody(body),
+    ] = group.children().as_slice()
+    else {
+        panic!("Expected 2 colspecs, a head, and a body: {group:?}");
+    };
+    assert_eq!(spec1.extra().colwidth.as_deref(), Some("12"));
+    assert_eq!(spec2.extra().colwidth.as_deref(), Some("5"));
+    assert_eq!(head.children().len(), 1);
+    assert_eq!(head.children()[0].children().len(), 2);
+
+    let rows = body.children();
+    assert_eq!(rows.len(), 3, "Should have 3 body rows: {rows:?}");
+    let colspan = &rows[0].children()[0];
+    assert_eq!(colspan.extra().morecols, Some(1));
+    assert_eq!(colspan.extra().morerows, None);
+    assert_eq!(rows[1].children().len(), 2);
+    let rowspan = &rows[1].children()[0];
+    assert_eq!(rowspan.extra().morerows, Some(1));
+    assert_eq!(
+        rowspan.children(),
+        &vec![
+            e::Paragraph::with_children(vec!["Spans two".into(), " ".into(), "rows".into()]).into()
+        ]
+    );
+    assert_eq!(rows[2].children().len(), 1);
+    assert!(matches!(
+        rows[2].children()[0].children().as_slice(),
+        [c::BodyElement::BulletList(_)]
+    ));
+}
+
+#[test]
+fn convert_malformed_grid_table() {
+    let doctree = parse("+--+\n| a\n+--+\n\nAfter.\n").unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "{lvl0:?}");
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected an error message, not {:?}", lvl0[0]);
+    };
+    assert_eq!(msg.extra().level, Some(3));
+    assert_eq!(
+        msg.children(),
+        &vec![
+            e::Paragraph::with_children(vec![
+                "Malformed table.\nLines differ in length or are not terminated by a border."
+                    .into()
+            ])
+            .into(),
+            e::LiteralBlock::with_children(vec!["+--+\n| a\n+--+".into()]).into(),
+        ]
+    );
+    assert!(matches!(
+        ssubel_to_body_element(&lvl0[1]),
+        c::BodyElement::Paragraph(_)
+    ));
+}
+
+const GRID_TABLE_TITLE: &str = "\
++-------+
+| Title |
+| ===== |
++-------+
+";
+
+#[test]
+fn convert_grid_table_title() {
+    let doctree = parse(GRID_TABLE_TITLE).unwrap();
+    let lvl0 = doctree.children();
+    let table = body_element_to_table(ssubel_to_body_element(&lvl0[0]));
+    let [c::SubTable::TableGroup(group)] = table.children().as_slice() else {
+        panic!("Expected a single table group: {table:?}");
+    };
+    let Some(c::SubTableGroup::TableBody(body)) = group.children().last() else {
+        panic!("Expected a body: {group:?}");
+    };
+    let entry = &body.children()[0].children()[0];
+    let [c::BodyElement::SystemMessage(msg)] = entry.children().as_slice() else {
+        panic!("Expected an error message: {entry:?}");
+    };
+    assert_eq!(msg.extra().level, Some(4));
+}
+
+const SIMPLE_TABLE: &str = "\
+=====  =====  ======
+   Inputs     Output
+------------  ------
+  A      B    A or B
+=====  =====  ======
+False  False  False
+True          True
+
+              and more
+=====  =====  ======
+";
+
+#[test]
+fn convert_simple_table() {
+    let doctree = parse(SIMPLE_TABLE).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 1, "Should be a single table: {lvl0:?}");
+    let table = body_element_to_table(ssubel_to_body_element(&lvl0[0]));
+    let [c::SubTable::TableGroup(group)] = table.children().as_slice() else {
+        panic!("Expected a single table group: {table:?}");
+    };
+    let widths: Vec<_> = group
+        .children()
+        .iter()
+        .filter_map(|c| match c {
+            c::SubTableGroup::TableColspec(spec) => spec.extra().colwidth.as_deref(),
+            _ => None,
+        })
+        .collect();
+    // The last column grows to fit “and more”
+    assert_eq!(widths, vec!["5", "5", "8"]);
+    let [
+        ..,
+        c::SubTableGroup::TableHead(head),
+        c::SubTableGroup::TableBody(body),
+    ] = group.children().as_slice()
+    else {
+        panic!("Expected a head and a body: {group:?}");
+    };
+
+    let head_rows = head.children();
+    assert_eq!(head_rows.len(), 2, "Should have 2 head rows: {head_rows:?}");
+    assert_eq!(head_rows[0].children().len(), 2);
+    assert_eq!(head_rows[0].children()[0].extra().morecols, Some(1));
+
+    let body_rows = body.children();
+    assert_eq!(body_rows.len(), 2, "Should have 2 body rows: {body_rows:?}");
+    let continued = &body_rows[1].children()[2];
+    assert_eq!(
+        continued.children().len(),
+        2,
+        "The continuation row should add a paragraph: {continued:?}"
+    );
+    assert!(body_rows[1].children()[1].children().is_empty());
+}
+
+const MALFORMED_SIMPLE_TABLE: &str = "\
+=====  =====
+a      b
+too_long  c
+=====  =====
+
+After.
+";
+
+#[test]
+fn convert_malformed_simple_table() {
+    let doctree = parse(MALFORMED_SIMPLE_TABLE).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "{lvl0:?}");
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected an error message, not {:?}", lvl0[0]);
+    };
+    assert_eq!(msg.extra().level, Some(3));
+    assert_eq!(
+        msg.children(),
+        &vec![
+            e::Paragraph::with_children(vec![
+                "Malformed table.\nText in column margin in table line 3.".into()
+            ])
+            .into(),
+            e::LiteralBlock::with_children(vec![MALFORMED_SIMPLE_TABLE[..46].into()]).into(),
+        ]
+    );
+    assert!(matches!(
+        ssubel_to_body_element(&lvl0[1]),
+        c::BodyElement::Paragraph(_)
+    ));
+}
+
+fn body_element_to_enumerated_list(bodyel: &c::BodyElement) -> &e::EnumeratedList {
+    match bodyel {
+        c::BodyElement::EnumeratedList(l) => l,
+        c => panic!("Expected EnumeratedList, not {c:?}"),
+    }
+}
+
+const ENUMERATED_LISTS: &str = "\
+3) three
+#) four
+
+i. roman
+ii. roman
+
+h. alpha
+i. alpha
+
+(1) one
+(3) non-sequential
+";
+
+#[test]
+fn convert_enumerated_lists() {
+    let doctree = parse(ENUMERATED_LISTS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 5, "Should be 5 lists: {lvl0:?}");
+    let lists: Vec<_> = lvl0
+        .iter()
+        .map(|l| body_element_to_enumerated_list(ssubel_to_body_element(l)))
+        .collect();
+    let attrs: Vec<_> = lists
+        .iter()
+        .map(|l| {
+            let extra = l.extra();
+            (
+                extra.enumtype.unwrap(),
+                extra.prefix.as_deref().unwrap(),
+                extra.suffix.as_deref().unwrap(),
+                extra.start,
+                l.children().len(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        attrs,
+        vec![
+            (at::EnumeratedListType::Arabic, "", ")", Some(3), 2),
+            (at::EnumeratedListType::LowerRoman, "", ".", None, 2),
+            // “i” continues an alphabetic list
+            (at::EnumeratedListType::LowerAlpha, "", ".", Some(8), 2),
+            (at::EnumeratedListType::Arabic, "(", ")", None, 1),
+            (at::EnumeratedListType::Arabic, "(", ")", Some(3), 1),
+        ]
+    );
+}
+
+#[test]
+fn convert_enumerated_lists_ambiguous() {
+    let doctree = parse("a) x\n\n(i) r1\n(ii) r2\n").unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "Should be 2 lists: {lvl0:?}");
+    let roman = body_element_to_enumerated_list(ssubel_to_body_element(&lvl0[1]));
+    // “(i)” has another format than “a)”, so it starts a roman list
+    assert_eq!(
+        roman.extra().enumtype,
+        Some(at::EnumeratedListType::LowerRoman)
+    );
+    assert_eq!(roman.extra().start, None);
+    assert_eq!(roman.children().len(), 2);
+}
+
+const DOCINFO: &str = "\
+Title
+=====
+
+:Version: 1.0
+:Authors: A. Uthor, B. Uthor
+:Abstract: Summary.
+:Audience: Everyone
+
+Body
+";
+
+#[test]
+fn convert_docinfo() {
+    let doctree = parse(DOCINFO).unwrap();
+    let section = ssubel_to_section(&doctree.children()[0]);
+    let [
+        c::StructuralSubElement::Title(_),
+        c::StructuralSubElement::Docinfo(docinfo),
+        abstract_,
+        body,
+    ] = section.children().as_slice()
+    else {
+        panic!("Expected title, docinfo, abstract and body: {section:?}");
+    };
+    let [
+        c::BibliographicElement::Version(version),
+        c::BibliographicElement::Authors(authors),
+        c::BibliographicElement::Field(audience),
+    ] = docinfo.children().as_slice()
+    else {
+        panic!("Expected version, authors, and a generic field: {docinfo:?}");
+    };
+    assert_eq!(version.children(), &vec!["1.0".into()]);
+    assert_eq!(authors.children().len(), 2);
+    assert_eq!(audience.classes(), &vec!["audience".to_owned()]);
+    assert!(matches!(
+        abstract_,
+        c::StructuralSubElement::SubStructure(s) if matches!(s.as_ref(), c::SubStructure::Topic(_))
+    ));
+    assert!(matches!(
+        ssubel_to_body_element(body),
+        c::BodyElement::Paragraph(_)
+    ));
+}
+
+const TRANSITIONS: &str = "\
+----
+
+Para
+
+----
+
+----
+
+Section
+=======
+
+Text
+
+----
+";
+
+fn system_message_text(ssubel: &c::StructuralSubElement) -> String {
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(ssubel) else {
+        panic!("Expected SystemMessage, not {ssubel:?}");
+    };
+    assert_eq!(msg.extra().level, Some(3));
+    let [c::BodyElement::Paragraph(p)] = msg.children().as_slice() else {
+        panic!("Expected a single paragraph: {msg:?}");
+    };
+    let [c::TextOrInlineElement::String(text)] = p.children().as_slice() else {
+        panic!("Expected plain text: {p:?}");
+    };
+    text.as_ref().clone()
+}
+
+fn is_transition(ssubel: &c::StructuralSubElement) -> bool {
+    matches!(
+        ssubel,
+        c::StructuralSubElement::SubStructure(s) if matches!(s.as_ref(), c::SubStructure::Transition(_))
+    )
+}
+
+#[test]
+fn convert_transitions() {
+    let doctree = parse(TRANSITIONS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 9, "Unexpected children: {lvl0:?}");
+    assert_eq!(
+        system_message_text(&lvl0[0]),
+        "Document or section may not begin with a transition."
+    );
+    assert!(is_transition(&lvl0[1]));
+    assert!(is_transition(&lvl0[3]));
+    assert!(system_message_text(&lvl0[4]).starts_with("At least one body element"));
+    assert!(is_transition(&lvl0[5]));
+    // The transition at the end of the section is moved after it …
+    assert_eq!(ssubel_to_section(&lvl0[6]).children().len(), 2);
+    assert!(is_transition(&lvl0[7]));
+    // … where it ends the document.
+    assert_eq!(
+        system_message_text(&lvl0[8]),
+        "Document may not end with a transition."
+    );
+}
+
+const TITLE_ERRORS: &str = "\
+Short
+===
+
+Too short
+=====
+
+=======
+Mismatch
+--------
+";
+
+#[test]
+fn convert_title_errors() {
+    let doctree = parse(TITLE_ERRORS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "Unexpected children: {lvl0:?}");
+    // A short underline for a long title is just text
+    let c::BodyElement::SystemMessage(info) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected an info message: {lvl0:?}");
+    };
+    assert_eq!((info.extra().level, info.extra().line), (Some(1), Some(2)));
+    assert_eq!(
+        ssubel_to_body_element(&lvl0[1]),
+        &e::Paragraph::with_children(vec!["Short".into(), " ".into(), "===".into()]).into()
+    );
+    // A longer one gives a warning in the section
+    let section = ssubel_to_section(&lvl0[2]).children();
+    assert_eq!(section.len(), 3, "Unexpected children: {section:?}");
+    let c::BodyElement::SystemMessage(warning) = ssubel_to_body_element(&section[1]) else {
+        panic!("Expected a warning: {section:?}");
+    };
+    assert_eq!(
+        (warning.extra().level, warning.extra().line),
+        (Some(2), Some(5))
+    );
+    // Mismatching adornments are an error
+    let c::BodyElement::SystemMessage(severe) = ssubel_to_body_element(&section[2]) else {
+        panic!("Expected a severe error: {section:?}");
+    };
+    assert_eq!(
+        (severe.extra().level, severe.extra().line),
+        (Some(4), Some(7))
+    );
+    assert!(matches!(
+        severe.children().as_slice(),
+        [
+            c::BodyElement::Paragraph(_),
+            c::BodyElement::LiteralBlock(_)
+        ]
+    ));
+}
+
+const DIRECTIVES: &str = "\
+.. test-directive:: first
+   second
+   :Class: a
+           b
+   :flag:
+
+   Some *content*.
+
+.. unknown-directive::
+";
+
+#[test]
+fn convert_directives() {
+    let mut config = Config::default();
+    config.register_directive("test-directive", |d: &Directive| {
+        assert_eq!(d.arguments, "first\nsecond");
+        assert_eq!(
+            d.options,
+            vec![
+                ("class".to_owned(), "a\nb".to_owned()),
+                ("flag".to_owned(), String::new()),
+            ]
+        );
+        assert_eq!(d.option("flag"), Some(""));
+        assert_eq!(d.content, "Some *content*.\n");
+        Ok(d.parse_content()?.into_iter().map(Into::into).collect())
+    });
+    let doctree = config.parse(DIRECTIVES).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "Unexpected children: {lvl0:?}");
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected the parsed content: {lvl0:?}");
+    };
+    assert!(matches!(
+        p.children().as_slice(),
+        [_, c::TextOrInlineElement::Emphasis(_), _]
+    ));
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected an error for the unknown directive: {lvl0:?}");
+    };
+    assert_eq!((msg.extra().level, msg.extra().line), (Some(3), Some(9)));
+}
+
+const BUILTIN_DIRECTIVES: &str = "\
+.. note::
+
+   Hello
+   world
+
+   - a
+   - b
+
+.. epigraph::
+
+   Quote
+
+   -- Author
+";
+
+#[test]
+fn convert_builtin_directives() {
+    let doctree = parse(BUILTIN_DIRECTIVES).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "Unexpected children: {lvl0:?}");
+    let c::BodyElement::Note(note) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected Note, not {lvl0:?}");
+    };
+    let [c::BodyElement::Paragraph(p), c::BodyElement::BulletList(_)] = note.children().as_slice()
+    else {
+        panic!("Expected a paragraph and a list: {note:?}");
+    };
+    assert_eq!(
+        p.children(),
+        &vec!["Hello".into(), " ".into(), "world".into()]
+    );
+    let c::BodyElement::BlockQuote(quote) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected BlockQuote, not {lvl0:?}");
+    };
+    assert_eq!(quote.classes(), &vec!["epigraph".to_owned()]);
+    assert!(matches!(
+        quote.children().as_slice(),
+        [
+            c::SubBlockQuote::BodyElement(_),
+            c::SubBlockQuote::Attribution(_)
+        ]
+    ));
+}
+
+#[test]
+fn convert_overridden_builtin_directive() {
+    let mut config = Config::default();
+    assert!(config.has_directive("note") && config.has_directive("image"));
+    config.register_directive("note", |d: &Directive| {
+        Ok(vec![
+            e::Paragraph::with_children(vec![d.content.clone().into()]).into(),
+        ])
+    });
+    let doctree = config.parse(".. note::\n\n   Custom\n").unwrap();
+    let lvl0 = doctree.children();
+    let [child] = lvl0.as_slice() else {
+        panic!("Expected a single element: {lvl0:?}");
+    };
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(child) else {
+        panic!("Expected the custom output, not {child:?}");
+    };
+    assert_eq!(p.children(), &vec!["Custom\n".into()]);
+}
+
+const FIGURE: &str = "\
+.. figure:: /path/to/img.jpg
+   :scale: 50
+   :class: border shadow
+   :figclass: wide
+   :align: right
+
+   Caption
+
+   Legend
+";
+
+#[test]
+fn convert_figure() {
+    let doctree = parse(FIGURE).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 1, "Should be a single figure: {lvl0:?}");
+    let c::BodyElement::Figure(figure) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected Figure, not {lvl0:?}");
+    };
+    assert_eq!(figure.classes(), &vec!["wide".to_owned()]);
+    assert_eq!(figure.extra().align, Some(at::AlignH::Right));
+    let [
+        c::SubFigure::BodyElement(img),
+        c::SubFigure::Caption(caption),
+        c::SubFigure::Legend(legend),
+    ] = figure.children().as_slice()
+    else {
+        panic!("Expected image, caption, and legend: {figure:?}");
+    };
+    let img = body_element_to_image(img);
+    assert_eq!(img.extra().scale, Some(50));
+    assert_eq!(
+        img.classes(),
+        &vec!["border".to_owned(), "shadow".to_owned()]
+    );
+    assert_eq!(caption.children(), &vec!["Caption".into()]);
+    assert_eq!(legend.children().len(), 1);
+}
+
+const TOPICS: &str = "\
+.. sidebar:: Sidebar
+   :subtitle: Sub
+   :class: margin
+
+   .. topic:: Topic in sidebar
+
+      Text.
+
+.. topic:: Topic
+   :name: topic
+
+   .. topic:: Nested topic
+
+      Text.
+";
+
+#[test]
+fn convert_topics() {
+    let doctree = parse(TOPICS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "Should be a sidebar and a topic: {lvl0:?}");
+    let c::StructuralSubElement::SubStructure(sub) = &lvl0[0] else {
+        panic!("Expected SubStructure, not {:?}", lvl0[0]);
+    };
+    let c::SubStructure::Sidebar(sidebar) = sub.as_ref() else {
+        panic!("Expected Sidebar, not {sub:?}");
+    };
+    assert_eq!(sidebar.classes(), &vec!["margin".to_owned()]);
+    assert!(matches!(
+        sidebar.children().as_slice(),
+        [
+            c::SubSidebar::Title(_),
+            c::SubSidebar::Subtitle(_),
+            c::SubSidebar::Topic(_)
+        ]
+    ));
+
+    let c::StructuralSubElement::SubStructure(sub) = &lvl0[1] else {
+        panic!("Expected SubStructure, not {:?}", lvl0[1]);
+    };
+    let c::SubStructure::Topic(topic) = sub.as_ref() else {
+        panic!("Expected Topic, not {sub:?}");
+    };
+    assert_eq!(topic.names(), &vec![at::NameToken("topic".to_owned())]);
+    let [c::SubTopic::Title(_), c::SubTopic::BodyElement(msg)] = topic.children().as_slice() else {
+        panic!("Expected a title and an error: {topic:?}");
+    };
+    assert!(matches!(msg.as_ref(), c::BodyElement::SystemMessage(_)));
+}
+
+const TOPIC_TITLE_MARKUP: &str = "\
+Intro
+
+.. topic:: Title *em* :foo:`x`
+
+   Text.
+";
+
+#[test]
+fn convert_topic_title_markup() {
+    let doctree = parse(TOPIC_TITLE_MARKUP).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "Expected an error after the topic: {lvl0:?}");
+    let c::StructuralSubElement::SubStructure(sub) = &lvl0[1] else {
+        panic!("Expected SubStructure, not {:?}", lvl0[1]);
+    };
+    let c::SubStructure::Topic(topic) = sub.as_ref() else {
+        panic!("Expected Topic, not {sub:?}");
+    };
+    let c::SubTopic::Title(title) = &topic.children()[0] else {
+        panic!("Expected a title: {topic:?}");
+    };
+    assert!(matches!(
+        title.children().as_slice(),
+        [
+            _,
+            c::TextOrInlineElement::Emphasis(_),
+            _,
+            c::TextOrInlineElement::Problematic(_)
+        ]
+    ));
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl0[2]) else {
+        panic!("Expected an error for the unknown role: {lvl0:?}");
+    };
+    assert_eq!(msg.extra().line, Some(3));
+    assert_eq!(
+        system_message_text(&lvl0[2]),
+        "Unknown interpreted text role “foo”."
+    );
+}
+
+const CONTENTS: &str = "\
+.. contents:: Overview
+   :depth: 1
+   :backlinks: none
+
+First
+=====
+
+.. contents::
+   :local:
+
+Nested
+------
+
+Second
+======
+";
+
+fn list_item_reference(item: &e::ListItem) -> &e::Reference {
+    let Some(c::BodyElement::Paragraph(p)) = item.children().first() else {
+        panic!("Expected a paragraph: {item:?}");
+    };
+    let [c::TextOrInlineElement::Reference(r)] = p.children().as_slice() else {
+        panic!("Expected a reference: {p:?}");
+    };
+    r
+}
+
+#[test]
+fn convert_contents() {
+    let doctree = parse(CONTENTS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "Should be a topic and 2 sections: {lvl0:?}");
+    let c::StructuralSubElement::SubStructure(sub) = &lvl0[0] else {
+        panic!("Expected SubStructure, not {:?}", lvl0[0]);
+    };
+    let c::SubStructure::Topic(topic) = sub.as_ref() else {
+        panic!("Expected Topic, not {sub:?}");
+    };
+    assert_eq!(topic.classes(), &vec!["contents".to_owned()]);
+    let [c::SubTopic::Title(_), c::SubTopic::BodyElement(list)] = topic.children().as_slice()
+    else {
+        panic!("Expected a title and a list: {topic:?}");
+    };
+    let c::BodyElement::BulletList(list) = list.as_ref() else {
+        panic!("Expected a bullet list: {list:?}");
+    };
+    // Only one level deep
+    let refids: Vec<_> = list
+        .children()
+        .iter()
+        .map(|item| {
+            assert_eq!(item.children().len(), 1);
+            list_item_reference(item).extra().refid.clone().unwrap().0
+        })
+        .collect();
+    assert_eq!(refids, vec!["first", "second"]);
+
+    // The local table of contents is in the first section
+    let first = ssubel_to_section(&lvl0[1]).children();
+    let c::StructuralSubElement::Title(title) = &first[0] else {
+        panic!("Expected a title: {first:?}");
+    };
+    assert_eq!(title.extra().refid, None, "Backlinks are disabled");
+    let c::StructuralSubElement::SubStructure(sub) = &first[1] else {
+        panic!("Expected SubStructure, not {:?}", first[1]);
+    };
+    let c::SubStructure::Topic(local) = sub.as_ref() else {
+        panic!("Expected Topic, not {sub:?}");
+    };
+    let [c::SubTopic::BodyElement(list)] = local.children().as_slice() else {
+        panic!("Expected just a list: {local:?}");
+    };
+    let c::BodyElement::BulletList(list) = list.as_ref() else {
+        panic!("Expected a bullet list: {list:?}");
+    };
+    let entry = list_item_reference(&list.children()[0]);
+    assert_eq!(entry.extra().refid, Some(at::ID("nested".to_owned())));
+    // Backlinks default to the entry
+    let nested = ssubel_to_section(&first[2]).children();
+    let c::StructuralSubElement::Title(title) = &nested[0] else {
+        panic!("Expected a title: {nested:?}");
+    };
+    assert_eq!(title.extra().refid.as_ref(), entry.ids().first());
+}
+
+const SECTNUM: &str = "\
+.. sectnum::
+   :depth: 2
+   :suffix: )
+
+One
+===
+
+Sub
+---
+
+Subsub
+~~~~~~
+
+Two
+===
+";
+
+fn section_number(ssubel: &c::StructuralSubElement) -> Option<String> {
+    let c::StructuralSubElement::Title(title) = &ssubel_to_section(ssubel).children()[0] else {
+        panic!("Expected a title: {ssubel:?}");
+    };
+    match &title.children()[0] {
+        c::TextOrInlineElement::Generated(g) => {
+            assert_eq!(g.classes(), &vec!["sectnum".to_owned()]);
+            let [c::TextOrInlineElement::String(s)] = g.children().as_slice() else {
+                panic!("Expected text: {g:?}");
+            };
+            Some(s.trim_end_matches('\u{a0}').to_string())
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn convert_sectnum() {
+    let doctree = parse(SECTNUM).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(
+        lvl0.len(),
+        2,
+        "The pending element should be removed: {lvl0:?}"
+    );
+    assert_eq!(section_number(&lvl0[0]).as_deref(), Some("1)"));
+    assert_eq!(section_number(&lvl0[1]).as_deref(), Some("2)"));
+    let lvl1 = ssubel_to_section(&lvl0[0]).children();
+    assert_eq!(section_number(&lvl1[1]).as_deref(), Some("1.1)"));
+    let lvl2 = ssubel_to_section(&lvl1[1]).children();
+    assert_eq!(section_number(&lvl2[1]), None, "Deeper than :depth:");
+}
+
+const INCLUDE: &str = "\
+.. include:: doc/part.rst
+
+.. include:: code.py
+   :code: python
+   :start-after: # start
+   :end-before: # end
+
+.. include:: lines.txt
+   :literal:
+   :start-line: 1
+   :end-line: -1
+
+.. include:: missing.rst
+";
+
+#[test]
+fn convert_include() {
+    let mut config = Config::default();
+    config.set_include_loader(|path: &Path| {
+        Ok(match path.to_str().unwrap() {
+            "doc/part.rst" => "Part\n----\n\nText.\n\n.. include:: ../doc/part.rst\n",
+            "code.py" => "a = 1\n# start\nb = 2\n# end\n",
+            "lines.txt" => "1\n2\n3\n4\n",
+            _ => anyhow::bail!("Not found"),
+        }
+        .as_bytes()
+        .to_vec())
+    });
+    let doctree = config.parse(INCLUDE).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 4, "{lvl0:?}");
+
+    // Included sections are inserted, nested errors point to the included file
+    let part = PathBuf::from("doc/part.rst");
+    let section = ssubel_to_section(&lvl0[0]);
+    assert_eq!(section.source(), &Some(part.clone()));
+    let c::StructuralSubElement::SubStructure(sub) = &section.children()[2] else {
+        panic!("Expected SubStructure, not {:?}", section.children()[2]);
+    };
+    let c::SubStructure::BodyElement(b) = sub.as_ref() else {
+        panic!("Expected BodyElement, not {sub:?}");
+    };
+    let c::BodyElement::SystemMessage(msg) = b.as_ref() else {
+        panic!("Expected a recursion error, not {b:?}");
+    };
+    assert_eq!(msg.source(), &Some(part));
+    assert_eq!(msg.extra().line, Some(6));
+
+    let c::BodyElement::LiteralBlock(code) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected a literal block, not {:?}", lvl0[1]);
+    };
+    assert_eq!(
+        code.classes(),
+        &vec!["code".to_owned(), "python".to_owned()]
+    );
+    assert_eq!(code.children(), &vec!["\nb = 2".into()]);
+
+    let c::BodyElement::LiteralBlock(lines) = ssubel_to_body_element(&lvl0[2]) else {
+        panic!("Expected a literal block, not {:?}", lvl0[2]);
+    };
+    assert_eq!(lines.children(), &vec!["2\n3\n".into()]);
+
+    let c::BodyElement::SystemMessage(_) = ssubel_to_body_element(&lvl0[3]) else {
+        panic!("Expected an error for the missing file, not {:?}", lvl0[3]);
+    };
+}
+
+const INCLUDE_SECTIONS: &str = "\
+Main
+====
+
+.. include:: part.rst
+
+Sub
+---
+";
+
+/// Unlike in docutils, included titles don’t continue the including file’s title styles.
+#[test]
+fn convert_include_sections() {
+    let mut config = Config::default();
+    config.set_include_loader(|_: &Path| Ok(b"Part\n====\n\nText.\n".to_vec()));
+    let doctree = config.parse(INCLUDE_SECTIONS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 1, "Should be a single section: {lvl0:?}");
+    let main = ssubel_to_section(&lvl0[0]);
+    let [
+        c::StructuralSubElement::Title(_),
+        c::StructuralSubElement::SubStructure(part),
+        c::StructuralSubElement::SubStructure(sub),
+    ] = main.children().as_slice()
+    else {
+        panic!("Expected title and two subsections: {main:?}");
+    };
+    // The included section is nested despite using the style of “Main”
+    let c::SubStructure::Section(part) = part.as_ref() else {
+        panic!("Expected the included section, not {part:?}");
+    };
+    assert_eq!(part.names(), &vec![at::NameToken("part".to_owned())]);
+    assert!(matches!(sub.as_ref(), c::SubStructure::Section(_)));
+}
+
+#[test]
+fn convert_include_literal() {
+    let mut config = Config::default();
+    config.set_include_loader(|_: &Path| Ok(b"\n  a\tb  \n\n".to_vec()));
+    let doctree = config.parse(".. include:: a.txt\n   :literal:\n").unwrap();
+    let lvl0 = doctree.children();
+    let c::BodyElement::LiteralBlock(block) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected a literal block, not {lvl0:?}");
+    };
+    // Only tabs are expanded, up to the next tab stop
+    assert_eq!(block.children(), &vec!["\n  a     b  \n\n".into()]);
+}
+
+#[test]
+fn convert_include_unknown_option() {
+    let mut config = Config::default();
+    config.set_include_loader(|_: &Path| Ok(b"text\n".to_vec()));
+    let doctree = config
+        .parse(".. include:: a.txt\n   :start-afer: text\n   :end-before: missing\n")
+        .unwrap();
+    let lvl0 = doctree.children();
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected an error, not {lvl0:?}");
+    };
+    // Reported instead of the missing “end-before” text
+    let [
+        c::BodyElement::Paragraph(p),
+        c::BodyElement::LiteralBlock(_),
+    ] = msg.children().as_slice()
+    else {
+        panic!("Expected a message and the directive source: {msg:?}");
+    };
+    assert_eq!(
+        p.children(),
+        &vec!["Error in “include” directive:\nUnknown option “start-afer”.".into()]
+    );
+}
+
+const DIRECTIVE_TABLES: &str = "\
+.. list-table:: Title
+   :header-rows: 1
+   :stub-columns: 1
+   :widths: 1 3
+
+   * - A
+     - B
+   * - C
+     - D is
+       long
+
+.. csv-table::
+   :header: x; y
+   :delim: ;
+   :escape: \\
+
+   1;\"two\\\"; three\"
+   4
+
+.. list-table::
+
+   * - 1
+     - 2
+   * - 3
+";
+
+fn table_group(table: &e::Table) -> &e::TableGroup {
+    match table.children().last() {
+        Some(c::SubTable::TableGroup(group)) => group,
+        _ => panic!("Expected a table group: {table:?}"),
+    }
+}
+
+fn table_rows(group: &e::TableGroup) -> (Vec<&e::TableRow>, Vec<&e::TableRow>) {
+    let (mut head, mut body) = (vec![], vec![]);
+    for child in group.children() {
+        match child {
+            c::SubTableGroup::TableHead(h) => head.extend(h.children()),
+            c::SubTableGroup::TableBody(b) => body.extend(b.children()),
+            c::SubTableGroup::TableColspec(_) => {}
+        }
+    }
+    (head, body)
+}
+
+fn entry_text(row: &e::TableRow, col: usize) -> String {
+    let [c::BodyElement::Paragraph(p)] = row.children()[col].children().as_slice() else {
+        panic!("Expected a paragraph: {row:?}");
+    };
+    p.children()
+        .iter()
+        .map(|t| match t {
+            c::TextOrInlineElement::String(s) => s.as_str(),
+            t => panic!("Expected text, not {t:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn convert_directive_tables() {
+    let doctree = parse(DIRECTIVE_TABLES).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "{lvl0:?}");
+
+    let c::BodyElement::Table(table) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected a table, not {:?}", lvl0[0]);
+    };
+    assert!(matches!(table.children()[0], c::SubTable::Title(_)));
+    let group = table_group(table);
+    let specs: Vec<_> = group
+        .children()
+        .iter()
+        .filter_map(|c| match c {
+            c::SubTableGroup::TableColspec(spec) => {
+                Some((spec.extra().colwidth.as_deref(), spec.extra().stub))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(specs, vec![(Some("1"), Some(true)), (Some("3"), None)]);
+    let (head, body) = table_rows(group);
+    assert_eq!((head.len(), body.len()), (1, 1));
+    assert_eq!(entry_text(head[0], 1), "B");
+    assert_eq!(entry_text(body[0], 1), "D is long");
+
+    let c::BodyElement::Table(table) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected a table, not {:?}", lvl0[1]);
+    };
+    let (head, body) = table_rows(table_group(table));
+    assert_eq!(head.len(), 1);
+    assert_eq!(entry_text(head[0], 0), "x");
+    assert_eq!(body.len(), 2);
+    assert_eq!(entry_text(body[0], 1), "two\"; three");
+    assert_eq!(body[1].children().len(), 2, "Short rows are padded");
+    assert_eq!(body[1].children()[1].children(), &vec![]);
+
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl0[2]) else {
+        panic!(
+            "Expected an error for a non-uniform list, not {:?}",
+            lvl0[2]
+        );
+    };
+    assert_eq!(msg.extra().line, Some(20));
+}
+
+const MATH: &str = "\
+Euler: :math:`e^{i\\pi} = -1`
+
+.. math::
+   :label: Sums
+   :class: big
+
+   a + b
+
+   c + d
+";
+
+#[test]
+fn convert_math() {
+    let doctree = parse(MATH).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "{lvl0:?}");
+
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected a paragraph, not {:?}", lvl0[0]);
+    };
+    let Some(c::TextOrInlineElement::Math(math)) = p.children().get(1) else {
+        panic!("Expected inline math: {p:?}");
+    };
+    assert_eq!(math.children(), &vec!["e^{i\\pi} = -1".to_owned()]);
+
+    let blocks: Vec<_> = lvl0[1..]
+        .iter()
+        .map(|el| match ssubel_to_body_element(el) {
+            c::BodyElement::MathBlock(block) => block,
+            el => panic!("Expected a math block, not {el:?}"),
+        })
+        .collect();
+    assert_eq!(blocks[0].children(), &vec!["a + b".to_owned()]);
+    assert_eq!(blocks[1].children(), &vec!["c + d".to_owned()]);
+    assert_eq!(blocks[0].ids(), &vec![at::ID::from("sums")]);
+    assert_eq!(
+        blocks[0].extra().label,
+        Some(at::NameToken("sums".to_owned()))
+    );
+    assert_eq!(blocks[1].extra().label, None);
+    assert_eq!(blocks[1].classes(), &vec!["big".to_owned()]);
+}
+
+const MATH_LABEL_ID: &str = "\
+Eq 1 a
+======
+
+.. math::
+   :label: Eq:1 (a)
+
+   x
+";
+
+#[test]
+fn convert_math_label_id() {
+    let doctree = parse(".. math::\n   :label: Eq:1 (a)\n\n   x\n").unwrap();
+    let c::BodyElement::MathBlock(block) = ssubel_to_body_element(&doctree.children()[0]) else {
+        panic!("Expected a math block: {doctree:?}");
+    };
+    assert_eq!(block.ids(), &vec![at::ID::from("eq-1-a")]);
+
+    let doctree = parse(MATH_LABEL_ID).unwrap();
+    let lvl0 = doctree.children();
+    let section = ssubel_to_section(&lvl0[0]);
+    assert_eq!(section.ids(), &vec![at::ID::from("eq-1-a")]);
+    let c::BodyElement::MathBlock(block) = ssubel_to_body_element(&section.children()[1]) else {
+        panic!("Expected a math block: {section:?}");
+    };
+    // Derived from the label like other IDs, without colliding with the section’s
+    assert_eq!(block.ids(), &vec![at::ID::from("eq-1-a-1")]);
+    assert_eq!(block.names(), &vec![at::NameToken("eq:1 (a)".to_owned())]);
+}
+
+const ROLES: &str = "\
+`Title` :sub:`2` `up`:sup: :code:`a\\`b` :pep:`8` :raw-html:`<br>` :twice:`x`
+
+An :unknown:`role`.
+";
+
+#[test]
+fn convert_roles() {
+    let mut config = Config::default();
+    config.register_role("twice", |t: &InterpretedText| {
+        Ok(vec![t.text.clone().into(), t.text.clone().into()])
+    });
+    let doctree = config.parse(ROLES).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "{lvl0:?}");
+
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected a paragraph, not {:?}", lvl0[0]);
+    };
+    let inlines: Vec<_> = p
+        .children()
+        .iter()
+        .filter(|i| !matches!(i, c::TextOrInlineElement::String(s) if s.as_str() == " "))
+        .collect();
+    assert!(matches!(
+        inlines[0],
+        c::TextOrInlineElement::TitleReference(_)
+    ));
+    assert!(matches!(inlines[1], c::TextOrInlineElement::Subscript(_)));
+    assert!(matches!(inlines[2], c::TextOrInlineElement::Superscript(_)));
+    let c::TextOrInlineElement::Literal(code) = inlines[3] else {
+        panic!("Expected a literal, not {:?}", inlines[3]);
+    };
+    assert_eq!(code.children(), &vec!["a`b".to_owned()]);
+    assert_eq!(code.classes(), &vec!["code".to_owned()]);
+    let c::TextOrInlineElement::Reference(pep) = inlines[4] else {
+        panic!("Expected a reference, not {:?}", inlines[4]);
+    };
+    assert_eq!(
+        pep.extra()
+            .refuri
+            .as_ref()
+            .map(ToString::to_string)
+            .as_deref(),
+        Some("https://peps.python.org/pep-0008/")
+    );
+    let c::TextOrInlineElement::RawInline(raw) = inlines[5] else {
+        panic!("Expected raw text, not {:?}", inlines[5]);
+    };
+    assert_eq!(raw.extra().format, vec![at::NameToken("html".to_owned())]);
+    assert_eq!(inlines.len(), 8, "The custom role results in two strings");
+
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected a paragraph, not {:?}", lvl0[1]);
+    };
+    assert!(matches!(
+        p.children()[1],
+        c::TextOrInlineElement::Problematic(_)
+    ));
+    assert_eq!(
+        system_message_text(&lvl0[2]),
+        "Unknown interpreted text role “unknown”."
+    );
+}
+
+const DOCUMENT_ROLES: &str = "\
+.. role:: custom
+.. role:: python(code)
+   :language: python
+.. role:: big(strong)
+   :class: big
+
+.. default-role:: custom
+
+`a` :python:`b` :big:`c`
+
+.. default-role::
+
+`d`
+";
+
+fn inline_classes(inline: &c::TextOrInlineElement) -> &[String] {
+    match inline {
+        c::TextOrInlineElement::Inline(e) => e.classes(),
+        c::TextOrInlineElement::Literal(e) => e.classes(),
+        c::TextOrInlineElement::Strong(e) => e.classes(),
+        c::TextOrInlineElement::TitleReference(e) => e.classes(),
+        inline => panic!("Unexpected inline element {inline:?}"),
+    }
+}
+
+#[test]
+fn convert_document_roles() {
+    let doctree = parse(DOCUMENT_ROLES).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "{lvl0:?}");
+
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected a paragraph, not {:?}", lvl0[0]);
+    };
+    let [a, _, b, _, c] = p.children().as_slice() else {
+        panic!("Expected three roles: {p:?}");
+    };
+    assert!(matches!(a, c::TextOrInlineElement::Inline(_)));
+    assert_eq!(inline_classes(a), ["custom"]);
+    assert_eq!(inline_classes(b), ["code", "python"]);
+    assert!(matches!(c, c::TextOrInlineElement::Strong(_)));
+    assert_eq!(inline_classes(c), ["big"]);
+
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected a paragraph, not {:?}", lvl0[1]);
+    };
+    assert!(matches!(
+        p.children()[0],
+        c::TextOrInlineElement::TitleReference(_)
+    ));
+
+    // Roles only apply to the document defining them
+    let doctree = parse(":python:`e`\n").unwrap();
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&doctree.children()[0]) else {
+        panic!("Expected a paragraph, not {:?}", doctree.children()[0]);
+    };
+    assert!(matches!(
+        p.children()[0],
+        c::TextOrInlineElement::Problematic(_)
+    ));
+}
+
+const CITATIONS: &str = "\
+Cited as [CIT2002]_, [cit2002]_ and [Missing]_.
+
+.. [CIT2002] The citation.
+";
+
+#[test]
+fn convert_citations() {
+    let doctree = parse(CITATIONS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "{lvl0:?}");
+
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[0]) else {
+        panic!("Expected a paragraph, not {:?}", lvl0[0]);
+    };
+    let refs: Vec<_> = p
+        .children()
+        .iter()
+        .filter_map(|i| match i {
+            c::TextOrInlineElement::CitationReference(r) => Some(r),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(refs.len(), 2, "{p:?}");
+    for r in &refs {
+        assert_eq!(r.extra().refid, Some(at::ID::from("cit2002")));
+    }
+    assert_eq!(refs[1].children(), &vec!["cit2002".into()]);
+    let Some(c::TextOrInlineElement::Problematic(problematic)) = p.children().get(5) else {
+        panic!("Expected the unknown reference to be problematic: {p:?}");
+    };
+    assert_eq!(problematic.children(), &vec!["[Missing]_".into()]);
+
+    let c::BodyElement::Citation(citation) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected a citation, not {:?}", lvl0[1]);
+    };
+    assert_eq!(citation.ids(), &vec![at::ID::from("cit2002")]);
+    assert_eq!(
+        citation.extra().backrefs,
+        vec![
+            at::ID::from("citation-reference-1"),
+            at::ID::from("citation-reference-2")
+        ]
+    );
+
+    let messages = ssubel_to_section(&lvl0[2]);
+    assert_eq!(messages.classes(), &vec!["system-messages".to_owned()]);
+    assert_eq!(
+        system_message_text(&messages.children()[1]),
+        "Unknown target name: “missing”."
+    );
+}
+
+const ANONYMOUS_HYPERLINKS: &str = "\
+`First`__ and second__.
+
+.. __: https://example.com/1
+__ https://example.org/2
+";
+
+fn paragraph_references(ssubel: &c::StructuralSubElement) -> Vec<&e::Reference> {
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(ssubel) else {
+        panic!("Expected a paragraph, not {ssubel:?}");
+    };
+    p.children()
+        .iter()
+        .filter_map(|i| match i {
+            c::TextOrInlineElement::Reference(r) => Some(r.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn convert_anonymous_hyperlinks() {
+    let doctree = parse(ANONYMOUS_HYPERLINKS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 3, "{lvl0:?}");
+    let refs = paragraph_references(&lvl0[0]);
+    assert!(refs.iter().all(|r| r.extra().anonymous));
+    let uris: Vec<_> = refs
+        .iter()
+        .map(|r| r.extra().refuri.as_ref().map(ToString::to_string))
+        .collect();
+    assert_eq!(
+        uris,
+        [
+            Some("https://example.com/1".to_owned()),
+            Some("https://example.org/2".to_owned())
+        ]
+    );
+
+    // With an extra reference, none can be resolved
+    let doctree = parse(&format!("{ANONYMOUS_HYPERLINKS}\nOne__ too many.\n")).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 5, "{lvl0:?}");
+    assert!(paragraph_references(&lvl0[0]).is_empty());
+    let messages = ssubel_to_section(&lvl0[4]);
+    assert_eq!(
+        system_message_text(&messages.children()[1]),
+        "Anonymous hyperlink mismatch: 3 references but 2 targets."
+    );
+}
+
+const INDIRECT_TARGETS: &str = "\
+Links to a_, d_, loop_ and missing_.
+
+.. _a: https://example.com/
+.. _`b c`: a_
+.. _d: `b c`_
+.. _loop: loop2_
+.. _loop2: loop_
+";
+
+#[test]
+fn convert_indirect_targets() {
+    let doctree = parse(INDIRECT_TARGETS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 7, "{lvl0:?}");
+    let refs = paragraph_references(&lvl0[0]);
+    assert_eq!(refs.len(), 2, "Only a_ and d_ resolve: {refs:?}");
+    for r in refs {
+        assert_eq!(
+            r.extra()
+                .refuri
+                .as_ref()
+                .map(ToString::to_string)
+                .as_deref(),
+            Some("https://example.com/")
+        );
+    }
+
+    let c::BodyElement::Target(target) = ssubel_to_body_element(&lvl0[3]) else {
+        panic!("Expected a target, not {:?}", lvl0[3]);
+    };
+    assert_eq!(target.extra().refname, vec![at::NameToken::from("b c")]);
+    assert!(target.extra().refuri.is_some());
+
+    let messages = ssubel_to_section(&lvl0[6]);
+    assert_eq!(
+        system_message_text(&messages.children()[1]),
+        "Circular indirect hyperlink targets: “loop” → “loop2” → “loop”."
+    );
+    assert_eq!(
+        system_message_text(&messages.children()[2]),
+        "Unknown target name: “missing”."
+    );
+}
+
+fn paragraph_problematics(ssubel: &c::StructuralSubElement) -> Vec<&e::Problematic> {
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(ssubel) else {
+        panic!("Expected a paragraph, not {ssubel:?}");
+    };
+    p.children()
+        .iter()
+        .filter_map(|i| match i {
+            c::TextOrInlineElement::Problematic(r) => Some(r.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn convert_unknown_reference_source() {
+    let doctree = parse("`Intro`_, `Intro\n  text`_, and intro_.\n").unwrap();
+    let lvl0 = doctree.children();
+    let texts: Vec<_> = paragraph_problematics(&lvl0[0])
+        .into_iter()
+        .map(|p| p.children().clone())
+        .collect();
+    assert_eq!(
+        texts,
+        [
+            vec!["`Intro`_".into()],
+            vec!["`Intro\n  text`_".into()],
+            vec!["intro_".into()],
+        ]
+    );
+}
+
+const INTERNAL_TARGETS: &str = "\
+Links to first_, `second`_ and chained_.
+
+.. _first:
+.. _second:
+
+Target paragraph.
+
+.. _chained:
+.. _external: https://example.com/
+
+.. _lonely:
+
+.. a comment
+";
+
+#[test]
+fn convert_internal_targets() {
+    let doctree = parse(INTERNAL_TARGETS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 8, "{lvl0:?}");
+    let refs = paragraph_references(&lvl0[0]);
+    let refids: Vec<_> = refs.iter().map(|r| r.extra().refid.as_ref()).collect();
+    let first = at::ID::from("first");
+    assert_eq!(refids, [Some(&first), Some(&first), None]);
+    assert!(refs[2].extra().refuri.is_some(), "{:?}", refs[2]);
+
+    // The paragraph after the targets gets their IDs
+    let c::BodyElement::Paragraph(p) = ssubel_to_body_element(&lvl0[3]) else {
+        panic!("Expected a paragraph, not {:?}", lvl0[3]);
+    };
+    assert_eq!(p.ids(), &vec![first.clone(), at::ID::from("second")]);
+    let c::BodyElement::Target(target) = ssubel_to_body_element(&lvl0[1]) else {
+        panic!("Expected a target, not {:?}", lvl0[1]);
+    };
+    assert!(target.ids().is_empty());
+    assert_eq!(target.extra().refid, Some(first));
+
+    // A comment doesn’t get IDs
+    let c::BodyElement::Target(target) = ssubel_to_body_element(&lvl0[6]) else {
+        panic!("Expected a target, not {:?}", lvl0[6]);
+    };
+    assert_eq!(target.ids(), &vec![at::ID::from("lonely")]);
+    assert_eq!(target.extra().refid, None);
+}
+
+#[test]
+fn convert_make_id() {
+    assert_eq!(
+        make_id("Straße & Café: 2nd Edition"),
+        "strasze-cafe-2nd-edition"
+    );
+    assert_eq!(make_id("  --Ærø  łódź--  "), "aero-lodz");
+    assert_eq!(make_id("1. Introduction"), "introduction");
+    assert_eq!(make_id("42"), "");
+}
+
+const SECTION_TARGETS: &str = "\
+Section *One*
+=============
+
+Links to `section one`_ and Twice_.
+
+Twice
+-----
+
+Twice
+-----
+
+.. _dup:
+.. _Dup:
+
+2024
+----
+";
+
+#[test]
+fn convert_section_targets() {
+    let doctree = parse(SECTION_TARGETS).unwrap();
+    let lvl0 = doctree.children();
+    assert_eq!(lvl0.len(), 2, "{lvl0:?}");
+    let section = ssubel_to_section(&lvl0[0]);
+    assert_eq!(section.ids(), &vec![at::ID::from("section-one")]);
+    assert_eq!(
+        section.names(),
+        &vec![at::NameToken("section one".to_owned())]
+    );
+
+    // The reference to the section resolves, the one to the duplicate name doesn’t
+    let lvl1 = section.children();
+    let refs = paragraph_references(&lvl1[1]);
+    assert_eq!(refs.len(), 1, "{lvl1:?}");
+    assert_eq!(refs[0].extra().refid, Some(at::ID::from("section-one")));
+
+    // Duplicate implicit names are moved to dupnames, IDs get a suffix
+    let sections: Vec<_> = lvl1[2..].iter().map(ssubel_to_section).collect();
+    let ids: Vec<_> = sections.iter().map(|s| s.ids()[0].0.as_str()).collect();
+    assert_eq!(ids, ["twice", "twice-1", "section-1"]);
+    let twice = vec![at::NameToken("twice".to_owned())];
+    assert!(sections[0].names().is_empty());
+    assert_eq!(sections[0].dupnames(), &twice);
+    assert_eq!(sections[1].dupnames(), &twice);
+
+    // Duplicate explicit names are reported before the second target
+    let lvl2 = sections[1].children();
+    assert_eq!(lvl2.len(), 4, "{lvl2:?}");
+    let c::BodyElement::Target(target) = ssubel_to_body_element(&lvl2[1]) else {
+        panic!("Expected a target, not {:?}", lvl2[1]);
+    };
+    assert_eq!(target.dupnames(), &vec![at::NameToken("dup".to_owned())]);
+    let c::BodyElement::SystemMessage(msg) = ssubel_to_body_element(&lvl2[2]) else {
+        panic!("Expected a system message, not {:?}", lvl2[2]);
+    };
+    assert_eq!(msg.extra().level, Some(2));
+    assert_eq!(msg.extra().backrefs, vec![at::ID::from("dup-1")]);
+
+    // Both targets point to the next section, skipping the message
+    assert_eq!(
+        sections[2].ids(),
+        &vec![
+            at::ID::from("section-1"),
+            at::ID::from("dup"),
+            at::ID::from("dup-1")
+        ]
+    );
+
+    // References to duplicate names are reported at the end of the document
+    let messages = ssubel_to_section(&lvl0[1]);
+    assert_eq!(
+        system_message_text(&messages.children()[1]),
+        "Duplicate target name, cannot be used as a unique reference: “twice”."
+    );
+}
+
+const SECTION_TARGET_SOURCE: &str = "\
+Links to `Twice`_ and `Once`_.
+
+Twice
+=====
+
+Twice
+=====
+
+Once
+====
+";
+
+#[test]
+fn convert_section_target_source() {
+    let doctree = parse(SECTION_TARGET_SOURCE).unwrap();
+    let lvl0 = doctree.children();
+    let refs = paragraph_references(&lvl0[0]);
+    assert_eq!(refs.len(), 1, "{lvl0:?}");
+    assert_eq!(refs[0].extra().refid, Some(at::ID::from("once")));
+    // The reference to the duplicate title is reported as written
+    let problematics = paragraph_problematics(&lvl0[0]);
+    assert_eq!(problematics.len(), 1, "{lvl0:?}");
+    assert_eq!(problematics[0].children(), &vec!["`Twice`_".into()]);
+}
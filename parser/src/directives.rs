@@ -0,0 +1,248 @@
+/*! Directives.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/restructuredtext.html#directives>
+ *
+ * The parser splits a directive into its name, arguments, options, and content.
+ * A [`DirectiveHandler`] registered for the name in the [`crate::Config`] of the parse
+ * then converts those into document elements.
+ *
+ * Unknown directives and directives whose handler fails are replaced by a system message.
+ * The built-in directives are:
+ *
+ * - [attention](https://docutils.sourceforge.io/docs/ref/rst/directives.html#specific-admonitions),
+ *   caution, danger, error, hint, important, note, tip, and warning
+ * - [admonition](https://docutils.sourceforge.io/docs/ref/rst/directives.html#generic-admonition)
+ * - [image](https://docutils.sourceforge.io/docs/ref/rst/directives.html#image)
+ * - [figure](https://docutils.sourceforge.io/docs/ref/rst/directives.html#figure)
+ * - [include](https://docutils.sourceforge.io/docs/ref/rst/directives.html#including-an-external-document-fragment)
+ * - [topic](https://docutils.sourceforge.io/docs/ref/rst/directives.html#topic)
+ * - [sidebar](https://docutils.sourceforge.io/docs/ref/rst/directives.html#sidebar)
+ * - [math](https://docutils.sourceforge.io/docs/ref/rst/directives.html#math)
+ * - [code](https://docutils.sourceforge.io/docs/ref/rst/directives.html#code) (`code-block`, `sourcecode`)
+ * - [epigraph](https://docutils.sourceforge.io/docs/ref/rst/directives.html#epigraph),
+ *   [highlights](https://docutils.sourceforge.io/docs/ref/rst/directives.html#highlights), and
+ *   [pull-quote](https://docutils.sourceforge.io/docs/ref/rst/directives.html#pull-quote)
+ * - [raw](https://docutils.sourceforge.io/docs/ref/rst/directives.html#raw-data-pass-through)
+ * - [list-table](https://docutils.sourceforge.io/docs/ref/rst/directives.html#list-table)
+ * - [csv-table](https://docutils.sourceforge.io/docs/ref/rst/directives.html#csv-table-1)
+ * - [contents](https://docutils.sourceforge.io/docs/ref/rst/directives.html#table-of-contents)
+ * - [sectnum](https://docutils.sourceforge.io/docs/ref/rst/directives.html#automatic-section-numbering)
+ * - [role](https://docutils.sourceforge.io/docs/ref/rst/directives.html#custom-interpreted-text-roles)
+ * - [default-role](https://docutils.sourceforge.io/docs/ref/rst/directives.html#setting-the-default-interpreted-text-role)
+ */
+
+mod admonitions;
+mod body;
+mod figure;
+mod include;
+mod math;
+mod parts;
+mod raw;
+mod role;
+mod tables;
+mod topic;
+
+use std::sync::Arc;
+
+pub(crate) use self::include::with_source_path;
+pub use self::include::{FileSystemLoader, IncludeLoader};
+
+use anyhow::{Error, bail};
+use document_tree::{
+    Element, ExtraAttributes, HasChildren, attribute_types as at, element_categories as c,
+    elements as e,
+};
+use pest::Parser;
+
+use crate::{
+    config::{Directives, with_current},
+    conversion::{convert_body, convert_inline_text, convert_structure, fully_normalize_name},
+    pest_rst::{RstParser, Rule},
+    report::{Level, system_message},
+};
+
+/// A directive as found in the source, before being converted by its handler.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directive {
+    /// The directive name in lowercase.
+    pub name: String,
+    /// The text following the “::” up to the options. Continuation lines are separated by newlines.
+    pub arguments: String,
+    /// The `:name: value` options following the arguments, in source order.
+    pub options: Vec<(String, String)>,
+    /// The unindented content block after the first blank line.
+    pub content: String,
+    /// The line the directive starts on.
+    pub line: usize,
+    /// The whole directive as found in the source.
+    pub source: String,
+}
+
+impl Directive {
+    /// Returns the value of the last option called `name`.
+    #[must_use]
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses the content as reStructuredText body elements.
+    ///
+    /// # Errors
+    /// Returns an error if parsing fails.
+    pub fn parse_content(&self) -> Result<Vec<c::BodyElement>, Error> {
+        parse_body(&self.content)
+    }
+}
+
+/// Converts directives into document elements.
+///
+/// Implemented for closures taking a [`Directive`].
+pub trait DirectiveHandler: Send + Sync {
+    /// Converts a directive into body elements, or topics and sidebars.
+    /// The latter are only allowed outside of body elements, and are reported as errors otherwise.
+    ///
+    /// # Errors
+    /// Errors are reported as a system message in place of the directive.
+    fn run(&self, directive: &Directive) -> Result<Vec<c::SubStructure>, Error>;
+}
+
+impl<F> DirectiveHandler for F
+where
+    F: Fn(&Directive) -> Result<Vec<c::SubStructure>, Error> + Send + Sync,
+{
+    fn run(&self, directive: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+        self(directive)
+    }
+}
+
+/// Directives supported out of the box. They can be replaced by registering another handler.
+pub(crate) fn builtin_directives() -> Directives {
+    let mut registry = Directives::new();
+    for name in admonitions::ADMONITIONS {
+        registry.insert(name.to_owned(), Arc::new(admonitions::admonition));
+    }
+    registry.insert("admonition".to_owned(), Arc::new(admonitions::generic));
+    registry.insert("image".to_owned(), Arc::new(figure::image));
+    registry.insert("figure".to_owned(), Arc::new(figure::figure));
+    registry.insert("include".to_owned(), Arc::new(include::include));
+    registry.insert("topic".to_owned(), Arc::new(topic::topic));
+    registry.insert("contents".to_owned(), Arc::new(parts::contents));
+    registry.insert("sectnum".to_owned(), Arc::new(parts::sectnum));
+    registry.insert("section-numbering".to_owned(), Arc::new(parts::sectnum));
+    registry.insert("sidebar".to_owned(), Arc::new(topic::sidebar));
+    registry.insert("math".to_owned(), Arc::new(math::math));
+    for name in ["code", "code-block", "sourcecode"] {
+        registry.insert(name.to_owned(), Arc::new(body::code));
+    }
+    for name in ["epigraph", "highlights", "pull-quote"] {
+        registry.insert(name.to_owned(), Arc::new(body::block_quote));
+    }
+    registry.insert("raw".to_owned(), Arc::new(raw::raw));
+    registry.insert("list-table".to_owned(), Arc::new(tables::list_table));
+    registry.insert("csv-table".to_owned(), Arc::new(tables::csv_table));
+    registry.insert("role".to_owned(), Arc::new(role::role));
+    registry.insert("default-role".to_owned(), Arc::new(role::default_role));
+    registry
+}
+
+/// Runs the handler registered for a directive.
+/// Unknown directives and failing handlers result in an error message containing the source.
+pub(crate) fn run_directive(directive: &Directive) -> Vec<c::SubStructure> {
+    let handler = with_current(|config| config.directive(&directive.name));
+    let message = match handler.map(|h| h.run(directive)) {
+        Some(Ok(elems)) => return elems,
+        Some(Err(e)) => format!("Error in “{}” directive:\n{e}.", directive.name),
+        None => format!("Unknown directive type “{}”.", directive.name),
+    };
+    vec![directive_error(directive, &message).into()]
+}
+
+/// Runs the handler registered for a directive inside of a body element.
+/// Topics and sidebars are not allowed there.
+pub(crate) fn run_body_directive(directive: &Directive) -> Vec<c::BodyElement> {
+    run_directive(directive)
+        .into_iter()
+        .map(|elem| {
+            if let c::SubStructure::BodyElement(b) = elem {
+                *b
+            } else {
+                let message = format!(
+                    "The “{}” directive may not be used within topics or body elements.",
+                    directive.name
+                );
+                directive_error(directive, &message).into()
+            }
+        })
+        .collect()
+}
+
+fn directive_error(directive: &Directive, message: &str) -> e::SystemMessage {
+    let mut msg = system_message(Level::Error, message, Some(directive.line));
+    msg.append_child(e::LiteralBlock::with_children(vec![
+        directive.source.trim_end().into(),
+    ]));
+    msg
+}
+
+/// Returns the content of a directive without arguments,
+/// which can start right after the “::”.
+fn content_with_arguments(d: &Directive) -> String {
+    match (d.arguments.is_empty(), d.content.is_empty()) {
+        (true, _) => d.content.clone(),
+        (false, true) => format!("{}\n", d.arguments),
+        (false, false) => format!("{}\n\n{}", d.arguments, d.content),
+    }
+}
+
+/// Sets the `:class:` and `:name:` options, failing for others that are not in `allowed`.
+fn set_common_options(
+    elem: &mut impl Element,
+    d: &Directive,
+    allowed: &[&str],
+) -> Result<(), Error> {
+    for (name, value) in &d.options {
+        match name.as_str() {
+            "class" => elem
+                .classes_mut()
+                .extend(value.split_whitespace().map(ToOwned::to_owned)),
+            "name" => elem
+                .names_mut()
+                .push(at::NameToken(fully_normalize_name(value))),
+            name if allowed.contains(&name) => {}
+            name => bail!("Unknown option “{name}”"),
+        }
+    }
+    Ok(())
+}
+
+/// Parses text as inline elements, e.g. a title given as directive argument.
+/// Also returns system messages for problems in the text, to be added after the element containing it.
+/// Their line numbers are relative to `line`, the line the text starts on.
+fn parse_inline_text(
+    text: &str,
+    line: usize,
+) -> Result<(Vec<c::TextOrInlineElement>, Vec<e::SystemMessage>), Error> {
+    let pairs = RstParser::parse(Rule::inline_text, text.trim())?;
+    let (inlines, mut messages) = convert_inline_text(pairs)?;
+    for msg in &mut messages {
+        if let Some(l) = &mut msg.extra_mut().line {
+            *l += line - 1;
+        }
+    }
+    Ok((inlines, messages))
+}
+
+/// Parses content that can contain topics, e.g. of sidebars.
+fn parse_structure(source: &str) -> Result<Vec<c::SubStructure>, Error> {
+    let pairs = RstParser::parse(Rule::document, source)?;
+    convert_structure(pairs)
+}
+
+fn parse_body(source: &str) -> Result<Vec<c::BodyElement>, Error> {
+    let pairs = RstParser::parse(Rule::document, source)?;
+    convert_body(pairs)
+}
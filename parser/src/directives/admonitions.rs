@@ -0,0 +1,78 @@
+/*! The admonition directives.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#admonitions>
+ *
+ * Specific admonitions like “note” contain body elements, which can start right after the “::”.
+ * The generic “admonition” directive additionally has a title given as argument.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::{Element, HasChildren, element_categories as c, elements as e};
+
+use super::{Directive, content_with_arguments, parse_body, parse_inline_text, set_common_options};
+use crate::conversion::make_id;
+
+/// The names of the specific admonitions.
+pub(super) const ADMONITIONS: [&str; 9] = [
+    "attention",
+    "caution",
+    "danger",
+    "error",
+    "hint",
+    "important",
+    "note",
+    "tip",
+    "warning",
+];
+
+pub(super) fn admonition(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let children = parse_body(&content_with_arguments(d))?;
+    if children.is_empty() {
+        bail!("Content block expected, none found");
+    }
+    let elem = match d.name.as_str() {
+        "attention" => with_options(e::Attention::with_children(children), d)?,
+        "caution" => with_options(e::Caution::with_children(children), d)?,
+        "danger" => with_options(e::Danger::with_children(children), d)?,
+        "error" => with_options(e::Error::with_children(children), d)?,
+        "hint" => with_options(e::Hint::with_children(children), d)?,
+        "important" => with_options(e::Important::with_children(children), d)?,
+        "note" => with_options(e::Note::with_children(children), d)?,
+        "tip" => with_options(e::Tip::with_children(children), d)?,
+        "warning" => with_options(e::Warning::with_children(children), d)?,
+        name => bail!("Unknown admonition type “{name}”"),
+    };
+    Ok(vec![elem.into()])
+}
+
+pub(super) fn generic(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    if d.arguments.is_empty() {
+        bail!("1 argument required, 0 supplied");
+    }
+    let body = parse_body(&d.content)?;
+    if body.is_empty() {
+        bail!("Content block expected, none found");
+    }
+    let (title, messages) = parse_inline_text(&d.arguments, d.line)?;
+    let mut children: Vec<c::SubTopic> = vec![e::Title::with_children(title).into()];
+    children.extend(body.into_iter().map(Into::into));
+    let mut admonition = e::Admonition::with_children(children);
+    set_common_options(&mut admonition, d, &[])?;
+    // Like docutils, admonitions without a class get one derived from their title
+    if admonition.classes().is_empty() {
+        admonition
+            .classes_mut()
+            .push(format!("admonition-{}", make_id(&d.arguments)));
+    }
+    let mut elems = vec![c::BodyElement::from(admonition).into()];
+    elems.extend(messages.into_iter().map(Into::into));
+    Ok(elems)
+}
+
+fn with_options<E>(mut elem: E, d: &Directive) -> Result<c::BodyElement, Error>
+where
+    E: Element + Into<c::BodyElement>,
+{
+    set_common_options(&mut elem, d, &[])?;
+    Ok(elem.into())
+}
@@ -0,0 +1,44 @@
+/*! The code and block quote directives.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#code>
+ * and <https://docutils.sourceforge.io/docs/ref/rst/directives.html#epigraph>
+ *
+ * Code is a literal block with the classes “code” and the language.
+ * Epigraphs, highlights, and pull-quotes are block quotes with their name as class.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::{Element, HasChildren, element_categories as c, elements as e};
+use pest::Parser;
+
+use super::{Directive, content_with_arguments, set_common_options};
+use crate::{
+    conversion::convert_block_quote_body,
+    pest_rst::{RstParser, Rule},
+};
+
+pub(super) fn code(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    if d.content.is_empty() {
+        bail!("Content block expected, none found");
+    }
+    let mut block = e::LiteralBlock::with_children(vec![d.content.as_str().into()]);
+    block.classes_mut().push("code".to_owned());
+    block
+        .classes_mut()
+        .extend(d.arguments.split_whitespace().map(ToOwned::to_owned));
+    // Line numbers are not supported, but don’t prevent showing the code
+    set_common_options(&mut block, d, &["number-lines"])?;
+    Ok(vec![c::BodyElement::from(block).into()])
+}
+
+pub(super) fn block_quote(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let content = content_with_arguments(d);
+    if content.trim().is_empty() {
+        bail!("Content block expected, none found");
+    }
+    let pairs = RstParser::parse(Rule::block_quote_body, &content)?;
+    let mut quote = e::BlockQuote::with_children(convert_block_quote_body(pairs)?);
+    quote.classes_mut().push(d.name.clone());
+    set_common_options(&mut quote, d, &[])?;
+    Ok(vec![c::BodyElement::from(quote).into()])
+}
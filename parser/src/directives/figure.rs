@@ -0,0 +1,73 @@
+/*! The image and figure directives.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#image>
+ * and <https://docutils.sourceforge.io/docs/ref/rst/directives.html#figure>
+ *
+ * An image has no content. A figure is an image with all its options, followed by an optional caption paragraph
+ * and a legend consisting of the remaining content.
+ */
+
+use std::mem::take;
+
+use anyhow::{Error, bail};
+use document_tree::{
+    Element, ExtraAttributes, HasChildren, element_categories as c, elements as e,
+    extra_attributes as a,
+};
+
+use super::Directive;
+use crate::conversion::set_image_option;
+
+pub(super) fn image(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let mut image = new_image(d)?;
+    for (name, value) in &d.options {
+        set_image_option(&mut image, name, value)?;
+    }
+    if !d.content.trim().is_empty() {
+        bail!("No content permitted");
+    }
+    Ok(vec![c::BodyElement::from(image).into()])
+}
+
+pub(super) fn figure(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let mut image = new_image(d)?;
+    let mut figure = e::Figure::default();
+    for (name, value) in &d.options {
+        match name.as_str() {
+            // the image’s width is not known here
+            "figwidth" if value == "image" => {}
+            "figwidth" => figure.extra_mut().width = Some(value.parse()?),
+            "figclass" => figure
+                .classes_mut()
+                .extend(value.split_whitespace().map(ToOwned::to_owned)),
+            "align" => figure.extra_mut().align = Some(value.parse()?),
+            _ => set_image_option(&mut image, name, value)?,
+        }
+    }
+    figure.append_child(image);
+
+    let mut content = d.parse_content()?.into_iter();
+    match content.next() {
+        Some(c::BodyElement::Paragraph(mut p)) => {
+            figure.append_child(e::Caption::with_children(take(p.children_mut())));
+        }
+        // an empty comment allows a legend without caption
+        Some(c::BodyElement::Comment(comment)) if comment.children().is_empty() => {}
+        Some(_) => bail!("Figure caption must be a paragraph or empty comment"),
+        None => {}
+    }
+    let legend: Vec<c::BodyElement> = content.collect();
+    if !legend.is_empty() {
+        figure.append_child(e::Legend::with_children(legend));
+    }
+    Ok(vec![figure.into()])
+}
+
+fn new_image(d: &Directive) -> Result<e::Image, Error> {
+    // whitespace in the URI is removed, as it can be split over several lines
+    let uri: String = d.arguments.split_whitespace().collect();
+    if uri.is_empty() {
+        bail!("1 argument required, 0 supplied");
+    }
+    Ok(e::Image::with_extra(a::Image::new(uri.parse()?)))
+}
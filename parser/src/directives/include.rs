@@ -0,0 +1,369 @@
+/*! The include directive.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#including-an-external-document-fragment>
+ *
+ * Files are read through the [`IncludeLoader`] of the [`crate::Config`] used for parsing,
+ * which reads from the file system by default.
+ * Relative paths are resolved against the directory of the including file,
+ * see [`crate::parse_with_path`].
+ *
+ * Included reStructuredText is parsed on its own,
+ * so its sections are inserted at the level of the directive,
+ * independently of the title styles used in the including file.
+ * This is a known limitation: docutils continues the title style hierarchy of the including file,
+ * so e.g. an included title styled like the enclosing section’s title would become its sibling.
+ */
+
+use std::{
+    cell::RefCell,
+    iter::{once, repeat_n},
+    mem::take,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Error, bail};
+use document_tree::{Element, HasChildren, element_categories as c, elements as e};
+use pest::Parser;
+
+use super::Directive;
+use crate::{
+    Config,
+    config::with_current,
+    conversion::convert_document,
+    pest_rst::{RstParser, Rule},
+    transforms::Transform,
+};
+
+/// Reads included files.
+///
+/// Implemented for closures taking a [`Path`], e.g. to serve files from memory:
+///
+/// ```
+/// use std::path::Path;
+/// use rst_parser::Config;
+///
+/// let mut config = Config::default();
+/// config.set_include_loader(|path: &Path| match path.to_str() {
+///     Some("greeting.rst") => Ok(b"Hello!\n".to_vec()),
+///     _ => anyhow::bail!("Not found"),
+/// });
+/// let doc = config.parse(".. include:: greeting.rst\n").unwrap();
+/// ```
+pub trait IncludeLoader: Send + Sync {
+    /// Reads the file at `path`, which is either absolute
+    /// or relative to the directory of the document given to [`crate::parse_with_path`].
+    ///
+    /// # Errors
+    /// Errors are reported as a system message in place of the directive.
+    fn load(&self, path: &Path) -> Result<Vec<u8>, Error>;
+}
+
+impl<F> IncludeLoader for F
+where
+    F: Fn(&Path) -> Result<Vec<u8>, Error> + Send + Sync,
+{
+    fn load(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        self(path)
+    }
+}
+
+/// Reads included files from the file system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSystemLoader;
+
+impl IncludeLoader for FileSystemLoader {
+    fn load(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        Ok(std::fs::read(path)?)
+    }
+}
+
+thread_local! {
+    /// The files currently being parsed, the innermost last.
+    static INCLUDE_STACK: RefCell<Vec<PathBuf>> = const { RefCell::new(vec![]) };
+}
+
+/// Runs `f` while parsing the file at `path`, so nested includes are resolved relative to it.
+pub(crate) fn with_source_path<T>(path: &Path, f: impl FnOnce() -> T) -> T {
+    struct Guard;
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            INCLUDE_STACK.with_borrow_mut(Vec::pop);
+        }
+    }
+
+    INCLUDE_STACK.with_borrow_mut(|stack| stack.push(normalize(path)));
+    let _guard = Guard;
+    f()
+}
+
+pub(super) fn include(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    if d.arguments.is_empty() {
+        bail!("1 argument required, 0 supplied");
+    }
+    for (name, _) in &d.options {
+        match name.as_str() {
+            "literal" | "code" | "encoding" | "start-line" | "end-line" | "start-after"
+            | "end-before" => {}
+            name => bail!("Unknown option “{name}”"),
+        }
+    }
+    let (path, text) = read_file(&d.arguments, d.option("encoding"))?;
+    let text = select(&text, d)?;
+
+    let literal = d.option("literal").is_some();
+    let code = d.option("code");
+    let mut elems: Vec<c::SubStructure> = if let Some(lang) = code {
+        // Like the content of a code directive, i.e. without the final line break
+        let text = text.lines().collect::<Vec<_>>().join("\n");
+        let mut block = e::LiteralBlock::with_children(vec![text.into()]);
+        block.classes_mut().push("code".to_owned());
+        block
+            .classes_mut()
+            .extend(lang.split_whitespace().map(ToOwned::to_owned));
+        vec![c::BodyElement::from(block).into()]
+    } else if literal {
+        let block = e::LiteralBlock::with_children(vec![text.into()]);
+        vec![c::BodyElement::from(block).into()]
+    } else {
+        let mut text = text;
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        with_source_path(&path, || parse_sections(&text))?
+    };
+
+    for elem in &mut elems {
+        set_source(elem, &path);
+    }
+    // Also point nested messages to the included file
+    let doc = e::Document::with_children(elems.into_iter().map(Into::into).collect());
+    let mut doc = SetSource(&path).transform(doc);
+    Ok(take(doc.children_mut())
+        .into_iter()
+        .filter_map(|child| match child {
+            c::StructuralSubElement::SubStructure(sub) => Some(*sub),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Reads a file relative to the including file with the given encoding, UTF-8 by default.
+/// Returns the resolved path and the decoded text.
+pub(super) fn read_file(
+    argument: &str,
+    encoding: Option<&str>,
+) -> Result<(PathBuf, String), Error> {
+    let path = resolve(argument)?;
+    let loader = with_current(Config::include_loader);
+    let bytes = loader
+        .load(&path)
+        .map_err(|e| Error::msg(format!("Problems with path “{}”: {e}", path.display())))?;
+    let text = decode(&bytes, encoding.unwrap_or("utf-8"))?;
+    Ok((path, text))
+}
+
+/// Resolves a path relative to the including file, detecting circular inclusion.
+fn resolve(argument: &str) -> Result<PathBuf, Error> {
+    if argument.starts_with('<') && argument.ends_with('>') {
+        bail!("Standard data files like “{argument}” are not supported");
+    }
+    INCLUDE_STACK.with_borrow(|stack| {
+        let path = match stack.last().and_then(|p| p.parent()) {
+            Some(dir) => normalize(&dir.join(argument)),
+            None => normalize(Path::new(argument)),
+        };
+        if stack.contains(&path) {
+            let chain: Vec<_> = stack
+                .iter()
+                .chain(once(&path))
+                .map(|p| p.display().to_string())
+                .collect();
+            bail!("Circular inclusion: {}", chain.join(" < "));
+        }
+        Ok(path)
+    })
+}
+
+/// Removes `.` and `..` components without accessing the file system,
+/// which the loader might not use.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn decode(bytes: &[u8], encoding: &str) -> Result<String, Error> {
+    Ok(match encoding.to_lowercase().replace('_', "-").as_str() {
+        "utf-8" | "utf8" => String::from_utf8(bytes.to_vec())?,
+        "utf-8-sig" => {
+            let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+            String::from_utf8(bytes.to_vec())?
+        }
+        "latin-1" | "latin1" | "iso-8859-1" => bytes.iter().copied().map(char::from).collect(),
+        "ascii" | "us-ascii" => {
+            if !bytes.is_ascii() {
+                bail!("Invalid ASCII");
+            }
+            bytes.iter().copied().map(char::from).collect()
+        }
+        encoding => bail!("Unsupported encoding “{encoding}”"),
+    })
+}
+
+/// Applies the line and text range options, expanding tabs.
+fn select(text: &str, d: &Directive) -> Result<String, Error> {
+    // Keep line breaks, so literal text stays unchanged
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let index = |name: &str, default: usize| -> Result<usize, Error> {
+        let Some(value) = d.option(name) else {
+            return Ok(default);
+        };
+        let idx: isize = value.parse()?;
+        // Python slice semantics: negative indices count from the end
+        Ok(match usize::try_from(idx) {
+            Ok(idx) => idx.min(lines.len()),
+            Err(_) => lines.len().saturating_sub(idx.unsigned_abs()),
+        })
+    };
+    let start = index("start-line", 0)?;
+    let end = index("end-line", lines.len())?.max(start);
+    let mut text = lines[start..end].concat();
+
+    if let Some(after) = d.option("start-after") {
+        let Some(idx) = text.find(after) else {
+            bail!("Problem with “start-after” option: Text not found.");
+        };
+        text.drain(..idx + after.len());
+    }
+    if let Some(before) = d.option("end-before") {
+        let Some(idx) = text.find(before) else {
+            bail!("Problem with “end-before” option: Text not found.");
+        };
+        text.truncate(idx);
+    }
+
+    Ok(expand_tabs(&text))
+}
+
+/// Replaces tabs by spaces up to the next tab stop every 8 columns, like Python’s `str.expandtabs`.
+fn expand_tabs(text: &str) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    for ch in text.chars() {
+        match ch {
+            '\t' => {
+                let width = 8 - column % 8;
+                expanded.extend(repeat_n(' ', width));
+                column += width;
+            }
+            '\n' | '\r' => {
+                expanded.push(ch);
+                column = 0;
+            }
+            _ => {
+                expanded.push(ch);
+                column += 1;
+            }
+        }
+    }
+    expanded
+}
+
+/// Parses included reStructuredText, which may contain sections.
+fn parse_sections(source: &str) -> Result<Vec<c::SubStructure>, Error> {
+    let pairs = RstParser::parse(Rule::document, source)?;
+    Ok(take(convert_document(pairs)?.children_mut())
+        .into_iter()
+        .filter_map(|child| match child {
+            c::StructuralSubElement::SubStructure(sub) => Some(*sub),
+            _ => None,
+        })
+        .collect())
+}
+
+fn set_source(elem: &mut c::SubStructure, path: &Path) {
+    let source = match elem {
+        c::SubStructure::Topic(e) => e.source_mut(),
+        c::SubStructure::Sidebar(e) => e.source_mut(),
+        c::SubStructure::Transition(e) => e.source_mut(),
+        c::SubStructure::Section(e) => e.source_mut(),
+        c::SubStructure::BodyElement(e) => return set_body_source(e, path),
+    };
+    source.get_or_insert_with(|| path.to_owned());
+}
+
+fn set_body_source(elem: &mut c::BodyElement, path: &Path) {
+    /// Gets the common source attribute of any body element.
+    macro_rules! source_mut {
+        ($($variant:ident),+) => {
+            match elem {
+                $(c::BodyElement::$variant(e) => e.source_mut(),)+
+            }
+        };
+    }
+    let source = source_mut!(
+        Paragraph,
+        LiteralBlock,
+        DoctestBlock,
+        MathBlock,
+        Rubric,
+        SubstitutionDefinition,
+        Comment,
+        Pending,
+        Target,
+        Raw,
+        Image,
+        Compound,
+        Container,
+        BulletList,
+        EnumeratedList,
+        DefinitionList,
+        FieldList,
+        OptionList,
+        LineBlock,
+        BlockQuote,
+        Admonition,
+        Attention,
+        Hint,
+        Note,
+        Caution,
+        Danger,
+        Error,
+        Important,
+        Tip,
+        Warning,
+        Footnote,
+        Citation,
+        SystemMessage,
+        Figure,
+        Table
+    );
+    source.get_or_insert_with(|| path.to_owned());
+}
+
+/// Sets the source of nested system messages that have none.
+struct SetSource<'a>(&'a Path);
+
+impl Transform for SetSource<'_> {
+    fn transform_system_message(
+        &mut self,
+        mut e: e::SystemMessage,
+    ) -> impl Iterator<Item = c::BodyElement> {
+        self.transform_children(&mut e, Self::transform_body_element);
+        e.source_mut().get_or_insert_with(|| self.0.to_owned());
+        once(e.into())
+    }
+}
@@ -0,0 +1,64 @@
+/*! The math directive.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#math>
+ *
+ * The content is LaTeX math, with one equation per block separated by blank lines.
+ * An argument is treated as the first equation.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::{
+    Element, ExtraAttributes, HasChildren, attribute_types as at, element_categories as c,
+    elements as e,
+};
+
+use super::Directive;
+use crate::conversion::fully_normalize_name;
+
+pub(super) fn math(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let mut equations: Vec<String> = vec![];
+    for text in [d.arguments.as_str(), d.content.as_str()] {
+        let mut equation: Vec<&str> = vec![];
+        for line in text.lines().chain([""]) {
+            if !line.trim().is_empty() {
+                equation.push(line);
+            } else if !equation.is_empty() {
+                equations.push(equation.join("\n"));
+                equation.clear();
+            }
+        }
+    }
+    if equations.is_empty() {
+        bail!("Content block expected, none found");
+    }
+
+    let mut blocks: Vec<e::MathBlock> = equations
+        .into_iter()
+        .map(|eq| e::MathBlock::with_children(vec![eq]))
+        .collect();
+    for (name, value) in &d.options {
+        match name.as_str() {
+            "class" => {
+                for block in &mut blocks {
+                    block
+                        .classes_mut()
+                        .extend(value.split_whitespace().map(ToOwned::to_owned));
+                }
+            }
+            "name" => blocks[0]
+                .names_mut()
+                .push(at::NameToken(fully_normalize_name(value))),
+            // As first name, the label gets its ID from `make_id` and is checked for duplicates in `AssignIds`
+            "label" => {
+                let label = at::NameToken(fully_normalize_name(value));
+                blocks[0].names_mut().insert(0, label.clone());
+                blocks[0].extra_mut().label = Some(label);
+            }
+            name => bail!("Unknown option “{name}”"),
+        }
+    }
+    Ok(blocks
+        .into_iter()
+        .map(|block| c::BodyElement::from(block).into())
+        .collect())
+}
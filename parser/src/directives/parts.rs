@@ -0,0 +1,83 @@
+/*! Directives for document parts.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#document-parts>
+ *
+ * They only leave a [`e::Pending`] element in the tree, which is resolved by a transform.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::{
+    Element, ExtraAttributes, HasChildren, attribute_types as at, element_categories as c,
+    elements as e,
+};
+
+use super::{Directive, parse_inline_text};
+use crate::conversion::fully_normalize_name;
+
+/// The table of contents, resolved by [`crate::transforms::Contents`].
+pub(super) fn contents(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let local = d.option("local").is_some();
+    let mut pending = e::Pending::default();
+    pending.extra_mut().transform = Some("contents".into());
+    let mut topic = e::Topic::default();
+    topic.classes_mut().push("contents".to_owned());
+    if local {
+        topic.classes_mut().push("local".to_owned());
+    }
+    for (name, value) in &d.options {
+        match name.as_str() {
+            "local" => {}
+            "class" => topic
+                .classes_mut()
+                .extend(value.split_whitespace().map(ToOwned::to_owned)),
+            "depth" | "backlinks" => {
+                if name == "depth" {
+                    value.parse::<usize>()?;
+                } else if !matches!(value.as_str(), "entry" | "top" | "none") {
+                    bail!("“{value}” unknown; choose from “top”, “entry”, or “none”");
+                }
+                let details = &mut pending.extra_mut().details;
+                details.push((name.as_str().into(), value.clone()));
+            }
+            name => bail!("Unknown option “{name}”"),
+        }
+    }
+
+    // Local tables of contents have no title by default
+    let title = match (d.arguments.as_str(), local) {
+        ("", true) => None,
+        ("", false) => Some("Contents"),
+        (title, _) => Some(title),
+    };
+    let mut messages = vec![];
+    if let Some(title) = title {
+        topic
+            .names_mut()
+            .push(at::NameToken(fully_normalize_name(title)));
+        let (title, title_messages) = parse_inline_text(title, d.line)?;
+        topic.append_child(e::Title::with_children(title));
+        messages = title_messages;
+    }
+    topic.append_child(pending);
+    let mut elems = vec![topic.into()];
+    elems.extend(messages.into_iter().map(Into::into));
+    Ok(elems)
+}
+
+/// Automatic section numbering, resolved by [`crate::transforms::SectNum`].
+pub(super) fn sectnum(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let mut pending = e::Pending::default();
+    pending.extra_mut().transform = Some("sectnum".into());
+    for (name, value) in &d.options {
+        match name.as_str() {
+            "depth" | "start" => {
+                value.parse::<usize>()?;
+            }
+            "prefix" | "suffix" => {}
+            name => bail!("Unknown option “{name}”"),
+        }
+        let details = &mut pending.extra_mut().details;
+        details.push((name.as_str().into(), value.clone()));
+    }
+    Ok(vec![c::BodyElement::from(pending).into()])
+}
@@ -0,0 +1,30 @@
+/*! The raw directive.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#raw-data-pass-through>
+ *
+ * The content is passed through to writers supporting one of the formats given as argument.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::{
+    ExtraAttributes, HasChildren, attribute_types as at, element_categories as c, elements as e,
+};
+
+use super::{Directive, set_common_options};
+
+pub(super) fn raw(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    if d.arguments.is_empty() {
+        bail!("1 argument required, 0 supplied");
+    }
+    if d.content.is_empty() {
+        bail!("Content block expected, none found");
+    }
+    let mut raw = e::Raw::with_children(vec![d.content.clone()]);
+    raw.extra_mut().format.extend(
+        d.arguments
+            .split_whitespace()
+            .map(|format| at::NameToken(format.to_lowercase())),
+    );
+    set_common_options(&mut raw, d, &[])?;
+    Ok(vec![c::BodyElement::from(raw).into()])
+}
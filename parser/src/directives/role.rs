@@ -0,0 +1,48 @@
+/*! The role and default-role directives.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#custom-interpreted-text-roles>
+ * and <https://docutils.sourceforge.io/docs/ref/rst/directives.html#setting-the-default-interpreted-text-role>
+ *
+ * Both change how interpreted text is converted for the rest of the document,
+ * and leave no elements in the tree.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::element_categories as c;
+
+use super::Directive;
+use crate::{
+    conversion::{define_role, set_default_role},
+    roles::CustomRole,
+};
+
+pub(super) fn role(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let argument = d.arguments.trim();
+    let (name, base) = match argument.strip_suffix(')').and_then(|a| a.split_once('(')) {
+        Some((name, base)) => (name.trim(), Some(base.trim().to_lowercase())),
+        None => (argument, None),
+    };
+    if name.is_empty() {
+        bail!("1 argument required, 0 supplied");
+    }
+    if name.contains(char::is_whitespace) || base.as_ref().is_some_and(String::is_empty) {
+        bail!(
+            "Role name must be a single word, optionally followed by a base role in parentheses, not “{argument}”"
+        );
+    }
+    let name = name.to_lowercase();
+
+    let mut options = d.options.clone();
+    // Elements of custom roles have the role name as class by default
+    if d.option("class").is_none() {
+        options.push(("class".to_owned(), name.clone()));
+    }
+    define_role(&name, CustomRole { base, options })?;
+    Ok(vec![])
+}
+
+pub(super) fn default_role(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let name = d.arguments.trim().to_lowercase();
+    set_default_role(Some(name.as_str()).filter(|n| !n.is_empty()))?;
+    Ok(vec![])
+}
@@ -0,0 +1,271 @@
+/*! The list-table and csv-table directives.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#list-table>
+ * and <https://docutils.sourceforge.io/docs/ref/rst/directives.html#csv-table-1>
+ *
+ * Both build the same table elements as grid and simple tables,
+ * with the stub columns marked in the column specifications.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::{
+    Element, HasChildren, attribute_types as at, element_categories as c, elements as e,
+};
+
+use super::{Directive, include::read_file, parse_inline_text};
+use crate::conversion::{build_uniform_table, convert_cell_text, fully_normalize_name};
+
+/// Options shared by both table directives.
+struct TableOptions {
+    header_rows: usize,
+    stub_columns: usize,
+    widths: Option<String>,
+}
+
+impl TableOptions {
+    /// Parses the shared options, failing for others that are not in `allowed`.
+    fn new(d: &Directive, allowed: &[&str]) -> Result<Self, Error> {
+        let mut options = TableOptions {
+            header_rows: 0,
+            stub_columns: 0,
+            widths: None,
+        };
+        for (name, value) in &d.options {
+            match name.as_str() {
+                "header-rows" => options.header_rows = value.parse()?,
+                "stub-columns" => options.stub_columns = value.parse()?,
+                "widths" => options.widths = Some(value.clone()),
+                "class" | "name" => {}
+                name if allowed.contains(&name) => {}
+                name => bail!("Unknown option “{name}”"),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Checks the data size and builds the table with title, class, and name.
+    /// Builds the table, followed by system messages for problems in its title.
+    fn build(
+        &self,
+        d: &Directive,
+        rows: Vec<Vec<Vec<c::BodyElement>>>,
+    ) -> Result<Vec<c::SubStructure>, Error> {
+        let n_cols = rows.first().map_or(0, Vec::len);
+        if self.header_rows >= rows.len() {
+            bail!(
+                "{} header row(s) specified but only {} row(s) of data supplied",
+                self.header_rows,
+                rows.len()
+            );
+        }
+        if self.stub_columns >= n_cols {
+            bail!(
+                "{} stub column(s) specified but only {n_cols} column(s) of data supplied",
+                self.stub_columns,
+            );
+        }
+        let mut classes = vec![];
+        let colwidths = match self.widths.as_deref() {
+            Some("auto") => {
+                classes.push("colwidths-auto".to_owned());
+                None
+            }
+            None | Some("grid") => Some(vec![100 / n_cols; n_cols]),
+            Some(widths) => {
+                let widths = widths
+                    .split(|ch: char| ch == ',' || ch.is_whitespace())
+                    .filter(|w| !w.is_empty())
+                    .map(str::parse)
+                    .collect::<Result<Vec<usize>, _>>()?;
+                if widths.len() != n_cols {
+                    bail!("“widths” do not match the number of columns in table ({n_cols})");
+                }
+                Some(widths)
+            }
+        };
+
+        let mut table = build_uniform_table(rows, colwidths, self.header_rows, self.stub_columns);
+        let mut messages = vec![];
+        if !d.arguments.is_empty() {
+            let (title, title_messages) = parse_inline_text(&d.arguments, d.line)?;
+            table
+                .children_mut()
+                .insert(0, e::Title::with_children(title).into());
+            messages = title_messages;
+        }
+        table.classes_mut().extend(classes);
+        if let Some(class) = d.option("class") {
+            table
+                .classes_mut()
+                .extend(class.split_whitespace().map(ToOwned::to_owned));
+        }
+        if let Some(name) = d.option("name") {
+            table
+                .names_mut()
+                .push(at::NameToken(fully_normalize_name(name)));
+        }
+        let mut elems = vec![c::BodyElement::from(table).into()];
+        elems.extend(messages.into_iter().map(Into::into));
+        Ok(elems)
+    }
+}
+
+pub(super) fn list_table(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let options = TableOptions::new(d, &[])?;
+    let mut content = d.parse_content()?;
+    let [c::BodyElement::BulletList(list)] = content.as_mut_slice() else {
+        bail!("Exactly one bullet list expected");
+    };
+    let mut rows = vec![];
+    for (i, item) in list.children_mut().iter_mut().enumerate() {
+        let [c::BodyElement::BulletList(row)] = item.children_mut().as_mut_slice() else {
+            bail!(
+                "Uniform two-level bullet list expected, but row {} does not contain a second-level bullet list",
+                i + 1
+            );
+        };
+        let cells: Vec<_> = row
+            .children_mut()
+            .iter_mut()
+            .map(|cell| std::mem::take(cell.children_mut()))
+            .collect();
+        if let Some(first) = rows.first().map(Vec::len)
+            && cells.len() != first
+        {
+            bail!(
+                "Uniform two-level bullet list expected, but row {} does not contain the same number of items as row 1 ({} vs {first})",
+                i + 1,
+                cells.len()
+            );
+        }
+        rows.push(cells);
+    }
+    options.build(d, rows)
+}
+
+pub(super) fn csv_table(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let mut options = TableOptions::new(
+        d,
+        &[
+            "header",
+            "file",
+            "encoding",
+            "delim",
+            "quote",
+            "escape",
+            "keepspace",
+        ],
+    )?;
+    let dialect = Dialect {
+        delim: match d.option("delim") {
+            None => ',',
+            Some(delim) => single_char("delim", delim)?,
+        },
+        quote: match d.option("quote") {
+            None => '"',
+            Some(quote) => single_char("quote", quote)?,
+        },
+        escape: d
+            .option("escape")
+            .map(|e| single_char("escape", e))
+            .transpose()?,
+        keepspace: d.option("keepspace").is_some(),
+    };
+
+    let data = match (d.content.trim().is_empty(), d.option("file")) {
+        (false, Some(_)) => bail!("CSV data must come from either content or file, not both"),
+        (true, None) => bail!("No CSV data supplied"),
+        (false, None) => d.content.clone(),
+        (true, Some(file)) => read_file(file, d.option("encoding"))?.1,
+    };
+    let mut records = vec![];
+    if let Some(header) = d.option("header") {
+        let header = dialect.parse(header)?;
+        options.header_rows += header.len();
+        records.extend(header);
+    }
+    records.extend(dialect.parse(&data)?);
+
+    // Short rows are padded with empty cells
+    let n_cols = records.iter().map(Vec::len).max().unwrap_or(0);
+    let rows = records
+        .into_iter()
+        .map(|mut record| {
+            record.resize(n_cols, String::new());
+            record
+                .iter()
+                .map(|cell| convert_cell_text(&format!("{cell}\n")))
+                .collect()
+        })
+        .collect::<Result<_, Error>>()?;
+    options.build(d, rows)
+}
+
+fn single_char(option: &str, value: &str) -> Result<char, Error> {
+    Ok(match value {
+        "tab" => '\t',
+        "space" => ' ',
+        value => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => ch,
+                _ => bail!("“{option}” option: single character required"),
+            }
+        }
+    })
+}
+
+/// How CSV data is formatted, like Python’s `csv.Dialect`.
+struct Dialect {
+    delim: char,
+    quote: char,
+    /// Escapes the next character. Without it, quotes are escaped by doubling them.
+    escape: Option<char>,
+    /// Keep whitespace after delimiters
+    keepspace: bool,
+}
+
+impl Dialect {
+    fn parse(&self, data: &str) -> Result<Vec<Vec<String>>, Error> {
+        let mut records = vec![];
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = data.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if Some(ch) == self.escape {
+                field.extend(chars.next());
+            } else if in_quotes {
+                if ch != self.quote {
+                    field.push(ch);
+                } else if self.escape.is_none() && chars.peek() == Some(&self.quote) {
+                    field.push(chars.next().unwrap_or(ch));
+                } else {
+                    in_quotes = false;
+                }
+            } else if ch == self.quote && field.is_empty() {
+                in_quotes = true;
+            } else if ch == self.delim {
+                record.push(std::mem::take(&mut field));
+                while !self.keepspace && chars.next_if_eq(&' ').is_some() {}
+            } else if ch == '\n' {
+                record.push(std::mem::take(&mut field));
+                // Blank lines don’t count as records
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            } else {
+                field.push(ch);
+            }
+        }
+        if in_quotes {
+            bail!("Error with CSV data: unexpected end of data");
+        }
+        if !field.is_empty() || !record.is_empty() {
+            record.push(field);
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,66 @@
+/*! The topic and sidebar directives.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#topic>
+ * and <https://docutils.sourceforge.io/docs/ref/rst/directives.html#sidebar>
+ *
+ * Both are titled blocks outside of the flow of the text.
+ * They can only be used at section level, and topics additionally in sidebars.
+ */
+
+use anyhow::{Error, bail};
+use document_tree::{HasChildren, element_categories as c, elements as e};
+
+use super::{Directive, parse_inline_text, parse_structure, set_common_options};
+use crate::report::{Level, system_message};
+
+pub(super) fn topic(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    if d.arguments.is_empty() {
+        bail!("1 argument required, 0 supplied");
+    }
+    let (title, messages) = parse_inline_text(&d.arguments, d.line)?;
+    let mut children: Vec<c::SubTopic> = vec![e::Title::with_children(title).into()];
+    children.extend(d.parse_content()?.into_iter().map(Into::into));
+    let mut topic = e::Topic::with_children(children);
+    set_common_options(&mut topic, d, &[])?;
+    let mut elems = vec![topic.into()];
+    elems.extend(messages.into_iter().map(Into::into));
+    Ok(elems)
+}
+
+pub(super) fn sidebar(d: &Directive) -> Result<Vec<c::SubStructure>, Error> {
+    let mut children: Vec<c::SubSidebar> = vec![];
+    let mut messages = vec![];
+    if !d.arguments.is_empty() {
+        let (title, title_messages) = parse_inline_text(&d.arguments, d.line)?;
+        children.push(e::Title::with_children(title).into());
+        messages.extend(title_messages);
+    }
+    if let Some(subtitle) = d.option("subtitle") {
+        let (subtitle, subtitle_messages) = parse_inline_text(subtitle, d.line)?;
+        children.push(e::Subtitle::with_children(subtitle).into());
+        messages.extend(subtitle_messages);
+    }
+    for elem in parse_structure(&d.content)? {
+        children.push(match elem {
+            c::SubStructure::Topic(t) => c::SubSidebar::Topic(t),
+            c::SubStructure::BodyElement(b) => (*b).into(),
+            c::SubStructure::Sidebar(_) => system_message(
+                Level::Error,
+                "The “sidebar” directive may not be used within a sidebar element.",
+                Some(d.line),
+            )
+            .into(),
+            c::SubStructure::Transition(_) | c::SubStructure::Section(_) => system_message(
+                Level::Severe,
+                "Unexpected section title or transition.",
+                Some(d.line),
+            )
+            .into(),
+        });
+    }
+    let mut sidebar = e::Sidebar::with_children(children);
+    set_common_options(&mut sidebar, d, &["subtitle"])?;
+    let mut elems = vec![sidebar.into()];
+    elems.extend(messages.into_iter().map(Into::into));
+    Ok(elems)
+}
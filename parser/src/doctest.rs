@@ -0,0 +1,42 @@
+use document_tree::{Document, elements as e};
+
+use crate::transforms::Visit;
+
+/// Iterate over all doctest blocks in a document, in document order.
+///
+/// This allows extracting examples to run them:
+///
+/// ```rust
+/// use document_tree::{HasChildren, element_categories as c};
+///
+/// let doc = rst_parser::parse(">>> 1 + 1\n2\n").unwrap();
+/// let sources: Vec<String> = rst_parser::doctest_blocks(&doc)
+///     .map(|block| {
+///         block
+///             .children()
+///             .iter()
+///             .map(|c| match c {
+///                 c::TextOrInlineElement::String(s) => s.as_str(),
+///                 _ => "",
+///             })
+///             .collect()
+///     })
+///     .collect();
+/// assert_eq!(sources, vec![">>> 1 + 1\n2\n"]);
+/// ```
+pub fn doctest_blocks(doc: &Document) -> impl Iterator<Item = &e::DoctestBlock> {
+    let mut collector = DoctestCollector::default();
+    collector.visit(doc);
+    collector.blocks.into_iter()
+}
+
+#[derive(Default)]
+struct DoctestCollector<'tree> {
+    blocks: Vec<&'tree e::DoctestBlock>,
+}
+
+impl<'tree> Visit<'tree> for DoctestCollector<'tree> {
+    fn visit_doctest_block(&mut self, e: &'tree e::DoctestBlock) {
+        self.blocks.push(e);
+    }
+}
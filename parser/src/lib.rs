@@ -1,35 +1,51 @@
 #![warn(clippy::pedantic)]
 
+mod config;
 mod conversion;
+pub mod directives;
+mod doctest;
 mod pair_ext_parse;
 mod pest_rst;
+mod report;
+pub mod roles;
 #[cfg(test)]
 pub mod tests;
 pub mod token;
 pub mod transforms;
 
+use std::path::Path;
+
 use anyhow::Error;
-use pest::Parser;
 
 use document_tree::Document;
 
-use self::conversion::convert_document;
-use self::pest_rst::{RstParser, Rule};
-use self::transforms::standard_transform;
+pub use self::config::Config;
+pub use self::doctest::doctest_blocks;
 
 /// Parse into a document tree and resolve sections, but not references.
+/// Uses the built-in directives and roles, see [`Config`].
 ///
 /// # Errors
 /// Returns an error if parsing fails.
 pub fn parse_only(source: &str) -> Result<Document, Error> {
-    let pairs = RstParser::parse(Rule::document, source)?;
-    convert_document(pairs)
+    Config::default().parse_only(source)
 }
 
 /// Parse into a document tree and resolve sections and references.
+/// Uses the built-in directives and roles, see [`Config`].
 ///
 /// # Errors
 /// Returns an error if parsing fails.
 pub fn parse(source: &str) -> Result<Document, Error> {
-    parse_only(source).map(standard_transform)
+    Config::default().parse(source)
+}
+
+/// Parse a document read from `path` into a document tree and resolve sections and references.
+///
+/// Unlike [`parse`], this resolves relative paths in include directives against the directory of `path`.
+///
+/// # Errors
+/// Returns an error if parsing fails.
+pub fn parse_with_path(source: &str, path: &Path) -> Result<Document, Error> {
+    Config::default().parse_with_path(source, path)
 }
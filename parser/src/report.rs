@@ -0,0 +1,41 @@
+/*! Construct system messages for problems found while parsing or transforming.
+ *
+ * Equivalent to the levels and messages of docutils’ `Reporter`,
+ * see <https://docutils.sourceforge.io/docs/user/config.html#report-level>
+ */
+
+use document_tree::{
+    HasChildren, attribute_types::NameToken, elements as e, extra_attributes::ExtraAttributes,
+};
+
+/// Severity of a system message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Info = 1,
+    Warning = 2,
+    Error = 3,
+    Severe = 4,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Warning => "WARNING",
+            Level::Error => "ERROR",
+            Level::Severe => "SEVERE",
+        }
+    }
+}
+
+/// Creates a system message containing a single paragraph.
+/// `line` is the line in the source the message refers to, if known.
+pub(crate) fn system_message(level: Level, message: &str, line: Option<usize>) -> e::SystemMessage {
+    let mut msg = e::SystemMessage::with_children(vec![
+        e::Paragraph::with_children(vec![message.into()]).into(),
+    ]);
+    msg.extra_mut().level = Some(level as usize);
+    msg.extra_mut().type_ = Some(NameToken::from(level.name()));
+    msg.extra_mut().line = line;
+    msg
+}
@@ -0,0 +1,314 @@
+/*! Interpreted text roles.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/roles.html>
+ *
+ * Interpreted text like `` :role:`text` `` or `` `text`:role: `` is converted
+ * by the [`RoleHandler`] registered for the role name in the [`crate::Config`] of the parse:
+ *
+ * ```
+ * use document_tree::{HasChildren, elements as e};
+ * use rst_parser::{Config, roles::InterpretedText};
+ *
+ * let mut config = Config::default();
+ * config.register_role("shout", |t: &InterpretedText| {
+ *     Ok(vec![e::Strong::with_children(vec![t.unescaped().to_uppercase().into()]).into()])
+ * });
+ * let doc = config.parse(":shout:`hello`\n").unwrap();
+ * ```
+ *
+ * Interpreted text without a role uses the default role, `title-reference`,
+ * unless a document changes it with the `default-role` directive.
+ * Documents can also define roles with the `role` directive,
+ * either generic ones resulting in [`e::Inline`] elements, or ones derived from other roles,
+ * whose handlers then receive the directive’s options.
+ * Unknown roles and roles whose handler fails are replaced by a problematic element,
+ * and a system message is added after the block containing them.
+ * The built-in roles are:
+ *
+ * - [emphasis](https://docutils.sourceforge.io/docs/ref/rst/roles.html#emphasis),
+ *   [strong](https://docutils.sourceforge.io/docs/ref/rst/roles.html#strong), and
+ *   [literal](https://docutils.sourceforge.io/docs/ref/rst/roles.html#literal)
+ * - [code](https://docutils.sourceforge.io/docs/ref/rst/roles.html#code)
+ * - [math](https://docutils.sourceforge.io/docs/ref/rst/roles.html#math)
+ * - [subscript](https://docutils.sourceforge.io/docs/ref/rst/roles.html#subscript) (`sub`) and
+ *   [superscript](https://docutils.sourceforge.io/docs/ref/rst/roles.html#superscript) (`sup`)
+ * - [title-reference](https://docutils.sourceforge.io/docs/ref/rst/roles.html#title-reference) (`title`, `t`)
+ * - [abbreviation](https://docutils.sourceforge.io/docs/ref/rst/roles.html#abbreviation) (`ab`) and
+ *   [acronym](https://docutils.sourceforge.io/docs/ref/rst/roles.html#acronym) (`ac`)
+ * - [pep-reference](https://docutils.sourceforge.io/docs/ref/rst/roles.html#pep-reference) (`pep`) and
+ *   [rfc-reference](https://docutils.sourceforge.io/docs/ref/rst/roles.html#rfc-reference) (`rfc`)
+ * - [raw](https://docutils.sourceforge.io/docs/ref/rst/roles.html#raw) as `raw-<format>`, e.g. `raw-html`
+ */
+
+use std::sync::Arc;
+
+use anyhow::{Error, bail};
+use document_tree::{
+    CommonAttributes, Element, ExtraAttributes, HasChildren, attribute_types as at,
+    element_categories as c, elements as e, extra_attributes as a, url::Url,
+};
+
+use crate::config::{Roles, with_current};
+
+/// The role used for interpreted text without an explicit role.
+pub const DEFAULT_ROLE: &str = "title-reference";
+
+/// Interpreted text as found in the source, before being converted by its role’s handler.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterpretedText {
+    /// The role name in lowercase.
+    pub role: String,
+    /// The text between the backquotes, including backslash escapes.
+    pub text: String,
+    /// The line the interpreted text starts on.
+    pub line: usize,
+    /// The whole interpreted text including the role as found in the source.
+    pub source: String,
+    /// The options of the `role` directive if the role was defined in the document,
+    /// e.g. `class` or `language`.
+    pub options: Vec<(String, String)>,
+}
+
+impl InterpretedText {
+    /// Returns the value of the last option called `name`.
+    #[must_use]
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the classes given by the `class` option.
+    #[must_use]
+    pub fn classes(&self) -> Vec<String> {
+        self.option("class")
+            .map(|c| c.split_whitespace().map(ToOwned::to_owned).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the text with backslash escapes removed.
+    #[must_use]
+    pub fn unescaped(&self) -> String {
+        let mut text = String::with_capacity(self.text.len());
+        let mut chars = self.text.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                // Escaped whitespace is removed entirely
+                '\\' => text.extend(chars.next().filter(|c| !c.is_whitespace())),
+                ch => text.push(ch),
+            }
+        }
+        text
+    }
+}
+
+/// Converts interpreted text into inline elements.
+///
+/// Implemented for closures taking an [`InterpretedText`].
+pub trait RoleHandler: Send + Sync {
+    /// Converts interpreted text into inline elements.
+    ///
+    /// # Errors
+    /// Errors are reported as a system message, and the text is marked as problematic.
+    fn run(&self, text: &InterpretedText) -> Result<Vec<c::TextOrInlineElement>, Error>;
+}
+
+impl<F> RoleHandler for F
+where
+    F: Fn(&InterpretedText) -> Result<Vec<c::TextOrInlineElement>, Error> + Send + Sync,
+{
+    fn run(&self, text: &InterpretedText) -> Result<Vec<c::TextOrInlineElement>, Error> {
+        self(text)
+    }
+}
+
+/// Roles supported out of the box. They can be replaced by registering another handler.
+pub(crate) fn builtin_roles() -> Roles {
+    let mut registry = Roles::new();
+    let mut insert = |names: &[&str], handler: Arc<dyn RoleHandler>| {
+        for name in names {
+            registry.insert((*name).to_owned(), handler.clone());
+        }
+    };
+    insert(&["emphasis"], wrap::<e::Emphasis>());
+    insert(&["strong"], wrap::<e::Strong>());
+    insert(&["subscript", "sub"], wrap::<e::Subscript>());
+    insert(&["superscript", "sup"], wrap::<e::Superscript>());
+    insert(
+        &["title-reference", "title", "t"],
+        wrap::<e::TitleReference>(),
+    );
+    insert(&["abbreviation", "ab"], wrap::<e::Abbreviation>());
+    insert(&["acronym", "ac"], wrap::<e::Acronym>());
+    insert(&["literal"], infallible(literal));
+    insert(&["code"], infallible(code));
+    insert(&["math"], infallible(math));
+    insert(&["pep-reference", "pep"], Arc::new(pep_reference));
+    insert(&["rfc-reference", "rfc"], Arc::new(rfc_reference));
+    insert(&["raw"], Arc::new(raw));
+    registry
+}
+
+/// A role defined by the `role` directive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CustomRole {
+    /// The role it is derived from. Generic roles without one result in [`e::Inline`] elements.
+    pub base: Option<String>,
+    /// The options passed to the handler of the base role.
+    pub options: Vec<(String, String)>,
+}
+
+impl CustomRole {
+    /// Runs the base role with this role’s options.
+    ///
+    /// # Errors
+    /// Returns an error message if the base role fails.
+    pub(crate) fn run(
+        &self,
+        text: &InterpretedText,
+    ) -> Result<Vec<c::TextOrInlineElement>, String> {
+        let text = InterpretedText {
+            options: self.options.clone(),
+            ..text.clone()
+        };
+        if let Some(base) = &self.base {
+            return run_role(&text, base);
+        }
+        let mut inline = e::Inline::with_children(vec![text.unescaped().into()]);
+        inline.classes_mut().extend(text.classes());
+        Ok(vec![inline.into()])
+    }
+}
+
+/// Returns whether `name` refers to a built-in or registered role.
+pub(crate) fn is_known(name: &str) -> bool {
+    with_current(|config| config.has_role(name)) || name.starts_with("raw-")
+}
+
+/// Runs the handler registered for the role `handler`, which differs from the role of the text
+/// for roles derived from it. `raw-<format>` roles are handled as raw text in that format.
+///
+/// # Errors
+/// Returns an error message for unknown roles and failing handlers.
+pub(crate) fn run_role(
+    text: &InterpretedText,
+    handler: &str,
+) -> Result<Vec<c::TextOrInlineElement>, String> {
+    let found = with_current(|config| config.role(handler));
+    let result = match (found, handler.strip_prefix("raw-")) {
+        (Some(found), _) => found.run(text),
+        (None, Some(format)) => Ok(vec![raw_format(text, format)]),
+        (None, None) => return Err(format!("Unknown interpreted text role “{}”.", text.role)),
+    };
+    result.map_err(|e| format!("Error in “{}” role:\n{e}.", text.role))
+}
+
+/// Creates a handler from a function producing a single element.
+fn infallible(f: fn(&InterpretedText) -> c::TextOrInlineElement) -> Arc<dyn RoleHandler> {
+    Arc::new(move |t: &InterpretedText| Ok(vec![f(t)]))
+}
+
+/// Creates a handler wrapping the unescaped text in an element of type `E`.
+fn wrap<E>() -> Arc<dyn RoleHandler>
+where
+    E: Element + HasChildren<c::TextOrInlineElement> + Into<c::TextOrInlineElement> + 'static,
+{
+    Arc::new(|t: &InterpretedText| {
+        let mut elem = E::with_children(vec![t.unescaped().into()]);
+        elem.classes_mut().extend(t.classes());
+        Ok(vec![elem.into()])
+    })
+}
+
+fn literal(t: &InterpretedText) -> c::TextOrInlineElement {
+    let mut literal = e::Literal::with_children(vec![t.unescaped()]);
+    literal.classes_mut().extend(t.classes());
+    literal.into()
+}
+
+/// Code has the class “code”, followed by the classes and language of derived roles.
+fn code(t: &InterpretedText) -> c::TextOrInlineElement {
+    let mut literal = e::Literal::with_children(vec![t.unescaped()]);
+    let classes = literal.classes_mut();
+    classes.push("code".to_owned());
+    classes.extend(t.classes());
+    if let Some(language) = t.option("language")
+        && !classes.iter().any(|c| c == language)
+    {
+        classes.push(language.to_owned());
+    }
+    literal.into()
+}
+
+/// Math keeps backslashes, as they are part of the LaTeX syntax.
+fn math(t: &InterpretedText) -> c::TextOrInlineElement {
+    let mut math = e::Math::with_children(vec![t.text.clone()]);
+    math.classes_mut().extend(t.classes());
+    math.into()
+}
+
+fn pep_reference(t: &InterpretedText) -> Result<Vec<c::TextOrInlineElement>, Error> {
+    let text = t.unescaped();
+    let number: u16 = match text.trim().parse() {
+        Ok(n) if n <= 9999 => n,
+        _ => bail!("PEP number must be a number from 0 to 9999; “{text}” is invalid"),
+    };
+    let url = format!("https://peps.python.org/pep-{number:04}/");
+    Ok(vec![reference(t, &url, format!("PEP {number}"))?.into()])
+}
+
+fn rfc_reference(t: &InterpretedText) -> Result<Vec<c::TextOrInlineElement>, Error> {
+    let text = t.unescaped();
+    let (number, fragment) = match text.split_once('#') {
+        Some((number, fragment)) => (number, Some(fragment)),
+        None => (text.as_str(), None),
+    };
+    let Ok(number) = number.trim().parse::<u32>() else {
+        bail!("RFC number must be a number greater than or equal to 1; “{text}” is invalid");
+    };
+    if number == 0 {
+        bail!("RFC number must be a number greater than or equal to 1; “{text}” is invalid");
+    }
+    let mut url = format!("https://tools.ietf.org/html/rfc{number}.html");
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    Ok(vec![reference(t, &url, format!("RFC {number}"))?.into()])
+}
+
+fn reference(t: &InterpretedText, url: &str, text: String) -> Result<e::Reference, Error> {
+    let mut reference = e::Reference::new(
+        CommonAttributes::default(),
+        a::Reference {
+            name: None,
+            refuri: Some(Url::parse_absolute(url)?),
+            refid: None,
+            refname: vec![],
+            anonymous: false,
+            rawsource: Some(t.source.clone()),
+        },
+        vec![text.into()],
+    );
+    reference.classes_mut().extend(t.classes());
+    Ok(reference)
+}
+
+/// The plain `raw` role needs a format, given by a derived role or by using e.g. `raw-html`.
+fn raw(t: &InterpretedText) -> Result<Vec<c::TextOrInlineElement>, Error> {
+    let Some(format) = t.option("format") else {
+        bail!("No format (Raw role must be customized)");
+    };
+    Ok(vec![raw_format(t, format)])
+}
+
+fn raw_format(t: &InterpretedText, format: &str) -> c::TextOrInlineElement {
+    let mut raw = e::RawInline::with_children(vec![t.text.clone()]);
+    raw.classes_mut().extend(t.classes());
+    raw.extra_mut()
+        .format
+        .push(at::NameToken(format.to_owned()));
+    raw.into()
+}
@@ -75,9 +75,10 @@ Title
 ",
         rule: Rule::title,
         tokens: [
-            title(0, 17, [ title_double(0, 17, [
+            title(0, 18, [ title_double(0, 18, [
                 adornments(0, 5),
                 line(6, 12, [ str(6, 11) ]),
+                adornments(12, 17),
             ]) ])
         ]
     };
@@ -106,6 +107,61 @@ fn two_targets() {
     };
 }
 
+#[test]
+fn indirect_targets() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+.. _a: b_
+.. _c: `d e`_
+",
+        rule: Rule::document,
+        tokens: [
+            target(0, 10, [
+                target_name_uq(4, 5),
+                link_target(7, 9, [ link_target_name(7, 9) ]),
+            ]),
+            target(10, 24, [
+                target_name_uq(14, 15),
+                link_target(17, 23, [ link_target_name(17, 23) ]),
+            ]),
+        ]
+    };
+}
+
+#[test]
+fn anonymous_hyperlinks() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+a__ `b c`__
+
+.. __: http://example.com
+__ https://example.org
+",
+        rule: Rule::document,
+        tokens: [
+            paragraph(0, 11, [
+                reference(0, 3, [
+                    reference_target(0, 3, [
+                        reference_target_uq(0, 1),
+                        reference_anonymous(2, 3),
+                    ]),
+                ]),
+                str(3, 4),
+                reference(4, 11, [
+                    reference_target(4, 11, [
+                        reference_target_qu(4, 10, [ reference_text(5, 8) ]),
+                        reference_anonymous(10, 11),
+                    ]),
+                ]),
+            ]),
+            anonymous_target(13, 39, [ link_target(20, 38) ]),
+            anonymous_target(39, 62, [ link_target(42, 61) ]),
+        ]
+    };
+}
+
 #[test]
 fn footnote() {
     parses_to! {
@@ -171,6 +227,32 @@ fn footnote_refs() {
     };
 }
 
+#[test]
+fn citations() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+See [Smith-2020]_.
+
+.. [Smith-2020] A
+   More
+",
+        rule: Rule::document,
+        tokens: [
+            paragraph(0, 18, [
+                str(0, 4),
+                citation_reference(4, 17, [ citation_label(5, 15) ]),
+                str(17, 18),
+            ]),
+            citation(20, 46, [
+                citation_label(24, 34),
+                line(36, 38, [ str(36, 37) ]),
+                paragraph(41, 45, [ str(41, 45) ]),
+            ]),
+        ]
+    };
+}
+
 #[test]
 fn inline_code_literal_with_underscore() {
     parses_to! {
@@ -199,17 +281,19 @@ fn admonitions() {
 ",
         rule: Rule::document,
         tokens: [
-            admonition_gen(0, 28, [
-                admonition_type(3, 7),
-                paragraph(13, 27, [ str(13, 27) ]),
+            directive(0, 28, [
+                directive_name(3, 7),
+                directive_line(13, 28),
             ]),
-            admonition(28, 73, [
-                line(43, 58, [ str(43, 57) ]),
-                paragraph(62, 71, [ str(62, 71) ]),
+            directive(28, 72, [
+                directive_name(31, 41),
+                directive_arg(44, 57),
+                directive_line_blank(58, 59),
+                directive_line(62, 72),
             ]),
-            admonition_gen(73, 100, [
-                admonition_type(76, 82),
-                line(84, 100, [ str(84, 99) ]),
+            directive(73, 100, [
+                directive_name(76, 82),
+                directive_arg(85, 99),
             ]),
         ]
     };
@@ -391,9 +475,10 @@ The end
 ",
         rule: Rule::document,
         tokens: [
-            block_quote_directive(0, 31, [
-                block_quote_type(3, 11),
-                paragraph(18, 29, [ str(18, 29) ]),
+            directive(0, 30, [
+                directive_name(3, 11),
+                directive_line_blank(14, 15),
+                directive_line(18, 30),
             ]),
             paragraph(31, 38, [ str(31, 38) ]),
         ]
@@ -448,16 +533,18 @@ The end
 ",
         rule: Rule::document,
         tokens: [
-            code_directive(0, 26, [
-                literal_lines(14, 26, [ literal_line(14, 26) ]),
+            directive(0, 26, [
+                directive_name(3, 7),
+                directive_line_blank(10, 11),
+                directive_line(14, 26),
             ]),
-            code_directive(27, 83, [
-                source(43, 49),
-                literal_lines(54, 83, [
-                    literal_line(54, 65),
-                    literal_line_blank(65, 66),
-                    literal_line(69, 83),
-                ]),
+            directive(27, 83, [
+                directive_name(30, 40),
+                directive_arg(43, 49),
+                directive_line_blank(50, 51),
+                directive_line(54, 65),
+                directive_line_blank(65, 66),
+                directive_line(69, 83),
             ]),
             paragraph(84, 91, [ str(84, 91) ]),
         ]
@@ -484,19 +571,19 @@ The end
 ",
         rule: Rule::document,
         tokens: [
-            raw_directive(0, 43, [
-                raw_output_format(9, 13),
-                raw_block(18, 43, [
-                    raw_line(18, 43),
-                ]),
+            directive(0, 43, [
+                directive_name(3, 6),
+                directive_arg(9, 13),
+                directive_line_blank(14, 15),
+                directive_line(18, 43),
             ]),
-            raw_directive(44, 100, [
-                raw_output_format(53, 57),
-                raw_block(62, 100, [
-                    raw_line(62, 79),
-                    raw_line_blank(79, 80),
-                    raw_line(83, 100),
-                ]),
+            directive(44, 100, [
+                directive_name(47, 50),
+                directive_arg(53, 57),
+                directive_line_blank(58, 59),
+                directive_line(62, 79),
+                directive_line_blank(79, 80),
+                directive_line(83, 100),
             ]),
             paragraph(101, 108, [ str(101, 108) ]),
         ]
@@ -718,3 +805,324 @@ paragraph
         ]
     }
 }
+
+#[allow(clippy::cognitive_complexity)]
+#[test]
+fn enumerated_list() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+(i) one
+(ii) two
+
+     body
+
+A. Einstein
+was here.
+",
+        rule: Rule::document,
+        tokens: [
+            enumerated_list(0, 29, [
+                enumerated_item(0, 8, [
+                    enumerator(0, 3),
+                    line(4, 8, [ str(4, 7) ]),
+                ]),
+                enumerated_item(8, 29, [
+                    enumerator(8, 12),
+                    line(13, 17, [ str(13, 16) ]),
+                    paragraph(23, 27, [ str(23, 27) ]),
+                ]),
+            ]),
+            // Not a list, as the next line is neither indented nor an enumerator
+            paragraph(29, 51, [ str(29, 40), ws_newline(40, 41), str(41, 50) ]),
+        ]
+    }
+}
+
+#[allow(clippy::cognitive_complexity)]
+#[test]
+fn definition_list() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+term
+  def
+
+*t* : cls
+  def
+",
+        rule: Rule::document,
+        tokens: [
+            definition_list(0, 28, [
+                definition_list_item(0, 12, [
+                    term(0, 4, [ term_str(0, 4) ]),
+                    definition(5, 12, [ paragraph(7, 10, [ str(7, 10) ]) ]),
+                ]),
+                definition_list_item(12, 28, [
+                    term(12, 15, [ emph(13, 14, [ str_nested(13, 14) ]) ]),
+                    classifier(18, 21, [ term_str(18, 21) ]),
+                    definition(22, 28, [ paragraph(24, 27, [ str(24, 27) ]) ]),
+                ]),
+            ]),
+        ]
+    }
+}
+
+#[test]
+fn definition_list_indented() {
+    // Indented lines are no terms, and trailing blank lines are allowed
+    parses_to! {
+        parser: RstParser,
+        input: "O::\n\n  a\n    b\n  c\n\n",
+        rule: Rule::document,
+        tokens: [
+            paragraph(0, 3, [ str(0, 3) ]),
+            paragraph(5, 19, [
+                str(5, 8), ws_newline(8, 9), str(9, 14), ws_newline(14, 15), str(15, 18),
+            ]),
+        ]
+    }
+}
+
+#[allow(clippy::cognitive_complexity)]
+#[test]
+fn field_list() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+:a: b
+:long name:
+   text
+",
+        rule: Rule::document,
+        tokens: [
+            field_list(0, 26, [
+                field(0, 6, [
+                    field_name(1, 2),
+                    line(4, 6, [ str(4, 5) ]),
+                ]),
+                field(6, 26, [
+                    field_name(7, 16),
+                    field_body(18, 26, [ paragraph(21, 25, [ str(21, 25) ]) ]),
+                ]),
+            ]),
+        ]
+    }
+}
+
+#[allow(clippy::cognitive_complexity)]
+#[test]
+fn option_list() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+-a, --b=C  d
+/V
+  e
+",
+        rule: Rule::document,
+        tokens: [
+            option_list(0, 20, [
+                option_list_item(0, 13, [
+                    option_group(0, 9, [
+                        option(0, 2, [ option_string(0, 2) ]),
+                        option(4, 9, [ option_string(4, 7), option_argument(8, 9) ]),
+                    ]),
+                    description(9, 13, [ description_line(11, 13, [ str(11, 12) ]) ]),
+                ]),
+                option_list_item(13, 20, [
+                    option_group(13, 15, [ option(13, 15, [ option_string(13, 15) ]) ]),
+                    description(15, 20, [ paragraph(18, 19, [ str(18, 19) ]) ]),
+                ]),
+            ]),
+        ]
+    }
+}
+
+#[test]
+fn line_block() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+| a
+|   b
+  c
+|
+",
+        rule: Rule::document,
+        tokens: [
+            line_block(0, 16, [
+                line_block_line(0, 4, [
+                    line_block_indent(2, 2),
+                    line_block_text(2, 4, [ str(2, 3) ]),
+                ]),
+                line_block_line(4, 14, [
+                    line_block_indent(6, 8),
+                    line_block_text(8, 14, [ str(8, 9), ws_newline(9, 10), str(12, 13) ]),
+                ]),
+                line_block_line(14, 16),
+            ]),
+        ]
+    }
+}
+
+#[test]
+fn doctest_block() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+>>> a
+b
+
+c
+",
+        rule: Rule::document,
+        tokens: [
+            doctest_block(0, 8, [ doctest_line(0, 6), doctest_line(6, 8) ]),
+            paragraph(9, 10, [ str(9, 10) ]),
+        ]
+    }
+}
+
+#[test]
+fn transition() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+a
+
+-----
+
+b
+",
+        rule: Rule::document,
+        tokens: [
+            paragraph(0, 1, [ str(0, 1) ]),
+            transition(3, 9),
+            paragraph(10, 11, [ str(10, 11) ]),
+        ]
+    }
+}
+
+#[test]
+fn directive() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+.. my-directive:: arg
+   :opt: val
+
+   content
+",
+        rule: Rule::document,
+        tokens: [
+            directive(0, 47, [
+                directive_name(3, 15),
+                directive_arg(18, 21),
+                directive_line(25, 35),
+                directive_line_blank(35, 36),
+                directive_line(39, 47),
+            ]),
+        ]
+    }
+}
+
+#[test]
+fn bullet_list_starting_with_block() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+* - a
+  - b
+* .. note:: c
+",
+        rule: Rule::bullet_list,
+        tokens: [
+            bullet_list(0, 26, [
+                bullet_item(0, 12, [
+                    bullet_block_line(2, 6),
+                    bullet_list(8, 12, [
+                        bullet_item(8, 12, [ line(10, 12, [ str(10, 11) ]) ]),
+                    ]),
+                ]),
+                bullet_item(12, 26, [ bullet_block_line(14, 26) ]),
+            ])
+        ]
+    };
+}
+
+#[test]
+fn interpreted_text() {
+    parses_to! {
+        parser: RstParser,
+        input: "A `b` :sup:`c` `d\\`e`:ab:\n",
+        rule: Rule::paragraph,
+        tokens: [
+            paragraph(0, 25, [
+                str(0, 2),
+                interpreted_text(2, 5, [ interpreted_content(3, 4) ]),
+                str(5, 6),
+                interpreted_text(6, 14, [ role_name(7, 10), interpreted_content(12, 13) ]),
+                str(14, 15),
+                interpreted_text(15, 25, [ interpreted_content(16, 20), role_name(22, 24) ]),
+            ])
+        ]
+    };
+}
+
+#[allow(clippy::cognitive_complexity)]
+#[test]
+fn grid_table() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
++---+---+
+| a | b |
++===+===+
+| c | d |
++---+---+
+
+The end
+",
+        rule: Rule::document,
+        tokens: [
+            grid_table(0, 50, [
+                grid_table_border(0, 10),
+                grid_table_line(10, 20),
+                grid_table_line(20, 30),
+                grid_table_line(30, 40),
+                grid_table_line(40, 50),
+            ]),
+            paragraph(51, 58, [ str(51, 58) ]),
+        ]
+    };
+}
+
+#[allow(clippy::cognitive_complexity)]
+#[test]
+fn simple_table() {
+    parses_to! {
+        parser: RstParser,
+        input: "\
+==  ==
+a   b
+==  ==
+c   d
+
+    e
+==  ==
+",
+        rule: Rule::document,
+        tokens: [
+            simple_table(0, 40, [
+                simple_table_border(0, 7),
+                simple_table_line(7, 13),
+                simple_table_border(13, 20),
+                simple_table_line(20, 26),
+                simple_table_blank(26, 27),
+                simple_table_line(27, 33),
+                simple_table_border(33, 40),
+            ]),
+        ]
+    };
+}
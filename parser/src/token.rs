@@ -10,6 +10,7 @@ pub enum BulletListType {
     HyphenBullet,
 }
 // 1, A, a, I, i
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EnumListChar {
     Arabic,
     AlphaUpper,
@@ -19,6 +20,7 @@ pub enum EnumListChar {
     Auto,
 }
 // 1., (1), 1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EnumListType {
     Period,
     ParenEnclosed,
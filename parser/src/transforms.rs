@@ -1,7 +1,19 @@
+mod assign_ids;
+mod contents;
+mod docinfo;
+mod propagate_targets;
+mod sectnum;
 mod standard;
 mod transform;
+mod transitions;
 mod visit;
 
+pub use self::assign_ids::AssignIds;
+pub use self::contents::Contents;
+pub use self::docinfo::DocInfo;
+pub use self::propagate_targets::PropagateTargets;
+pub use self::sectnum::SectNum;
 pub use self::standard::standard_transform;
 pub use self::transform::{IteratorMaker, Transform};
+pub use self::transitions::Transitions;
 pub use self::visit::Visit;
@@ -0,0 +1,225 @@
+/*! Give IDs to named elements and resolve duplicate names.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/restructuredtext.html#implicit-hyperlink-targets>
+ *
+ * Elements with names are hyperlink targets: explicit ones like `.. _name:`,
+ * citations, or directives with a `:name:` option, and implicit ones like section titles.
+ * Each of them gets an ID derived from its first usable name like docutils’ `make_id`,
+ * with a numeric suffix like `-1` if that ID is already taken.
+ *
+ * Names have to be unique. If several elements have the same name, it is moved to their `dupnames`:
+ * - Explicit targets take precedence over implicit ones.
+ * - Several implicit targets with the same name all lose it.
+ * - Several explicit targets with the same name all lose it, and a warning is added before the later one.
+ *   Only external targets with the same URI can share their name.
+ *
+ * Port of `set_id` and `set_duplicate_name_id` in <https://repo.or.cz/docutils.git/blob/HEAD:/docutils/docutils/nodes.py>
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    iter::once,
+};
+
+use document_tree::{
+    Element, ExtraAttributes, HasChildren,
+    attribute_types::{ID, NameToken},
+    element_categories as c, elements as e,
+    url::Url,
+};
+
+use super::Transform;
+use crate::{
+    conversion::make_id,
+    report::{Level, system_message},
+};
+
+/// Transform adding IDs to named elements and moving duplicate names to `dupnames`.
+#[derive(Debug, Default)]
+pub struct AssignIds {
+    /// IDs in use.
+    ids: HashSet<ID>,
+    /// Number of generated IDs by prefix.
+    counters: HashMap<String, usize>,
+    /// Elements having each name, in document order.
+    names: HashMap<NameToken, Vec<NameUse>>,
+    /// Set once all names are collected.
+    duplicates: Option<Duplicates>,
+}
+
+/// An element having a name.
+#[derive(Debug)]
+struct NameUse {
+    id: ID,
+    explicit: bool,
+    refuri: Option<Url>,
+}
+
+#[derive(Debug, Default)]
+struct Duplicates {
+    /// Names to move to `dupnames`, by element ID.
+    dupnames: HashSet<(ID, NameToken)>,
+    /// Duplicate explicit target names to warn about, by element ID.
+    warnings: HashMap<ID, NameToken>,
+}
+
+impl Duplicates {
+    /// Decides which elements keep a name.
+    fn find(names: &HashMap<NameToken, Vec<NameUse>>) -> Self {
+        let mut duplicates = Self::default();
+        let mut dupname = |u: &NameUse, name: &NameToken| {
+            duplicates.dupnames.insert((u.id.clone(), name.clone()));
+        };
+        let mut warnings = HashMap::new();
+        for (name, uses) in names {
+            let mut uses = uses.iter();
+            let Some(first) = uses.next() else {
+                continue;
+            };
+            // The element currently owning the name, if any
+            let mut owner = Some(first);
+            let mut explicit = first.explicit;
+            for u in uses {
+                let old_explicit = explicit;
+                explicit |= u.explicit;
+                if u.explicit && old_explicit {
+                    let same_uri =
+                        owner.is_some_and(|o| u.refuri.is_some() && o.refuri == u.refuri);
+                    if !same_uri {
+                        if let Some(old) = owner.take() {
+                            dupname(old, name);
+                        }
+                        warnings.insert(u.id.clone(), name.clone());
+                    }
+                    dupname(u, name);
+                } else if u.explicit {
+                    if let Some(old) = owner.replace(u) {
+                        dupname(old, name);
+                    }
+                } else {
+                    if let Some(old) = owner.filter(|_| !old_explicit) {
+                        dupname(old, name);
+                        owner = None;
+                    }
+                    dupname(u, name);
+                }
+            }
+        }
+        duplicates.warnings = warnings;
+        duplicates
+    }
+}
+
+impl AssignIds {
+    /// Returns an unused ID for an element with these names.
+    /// If the names don’t result in an ID, the prefix is the element type (e.g. `section`).
+    fn unique_id(&mut self, names: &[NameToken], tagname: &str) -> ID {
+        let mut base = String::new();
+        for name in names {
+            base = make_id(&name.0);
+            if !base.is_empty() && !self.ids.contains(&ID(base.clone())) {
+                return ID(base);
+            }
+        }
+        let prefix = if base.is_empty() { tagname } else { &base };
+        let counter = self.counters.entry(format!("{prefix}-")).or_default();
+        loop {
+            *counter += 1;
+            let id = ID(format!("{prefix}-{counter}"));
+            if !self.ids.contains(&id) {
+                return id;
+            }
+        }
+    }
+
+    /// Collects the names of an element, adding an ID if necessary.
+    /// Once names are collected, moves its duplicate names to `dupnames`
+    /// and returns a warning if it is a duplicate explicit target.
+    fn note(
+        &mut self,
+        e: &mut dyn Element,
+        tagname: &str,
+        explicit: bool,
+        refuri: Option<&Url>,
+    ) -> Option<e::SystemMessage> {
+        if let Some(duplicates) = &self.duplicates {
+            let id = e.ids().first()?.clone();
+            let names = std::mem::take(e.names_mut());
+            let (dupnames, names) = names
+                .into_iter()
+                .partition(|name| duplicates.dupnames.contains(&(id.clone(), name.clone())));
+            *e.names_mut() = names;
+            e.dupnames_mut().extend::<Vec<_>>(dupnames);
+            let name = duplicates.warnings.get(&id)?;
+            let mut msg = system_message(
+                Level::Warning,
+                &format!("Duplicate explicit target name: “{}”.", name.0),
+                None,
+            );
+            msg.extra_mut().backrefs.push(id);
+            return Some(msg);
+        }
+        if e.names().is_empty() {
+            self.ids.extend(e.ids().iter().cloned());
+            return None;
+        }
+        if e.ids().is_empty() {
+            let id = self.unique_id(e.names(), tagname);
+            e.ids_mut().push(id);
+        }
+        self.ids.extend(e.ids().iter().cloned());
+        let id = e.ids()[0].clone();
+        for name in e.names() {
+            self.names.entry(name.clone()).or_default().push(NameUse {
+                id: id.clone(),
+                explicit,
+                refuri: refuri.cloned(),
+            });
+        }
+        None
+    }
+}
+
+macro_rules! note_in {
+    ( $( $method:ident($elem:ident, $tagname:literal, $explicit:literal) -> $item:ty $(, $children:ident)?; )+ ) => { $(
+        fn $method(&mut self, mut e: e::$elem) -> impl Iterator<Item = $item> {
+            let message = self.note(&mut e, $tagname, $explicit, None);
+            $( self.transform_children(&mut e, Self::$children); )?
+            let message = message.map(|msg| c::BodyElement::from(msg).into());
+            message.into_iter().chain(once(e.into()))
+        }
+    )+ };
+}
+
+impl Transform for AssignIds {
+    fn transform(&mut self, mut d: e::Document) -> e::Document {
+        self.transform_children(&mut d, Self::transform_structural_sub_element);
+        self.duplicates = Some(Duplicates::find(&self.names));
+        self.transform_children(&mut d, Self::transform_structural_sub_element);
+        d
+    }
+
+    fn transform_target(&mut self, mut e: e::Target) -> impl Iterator<Item = c::BodyElement> {
+        let refuri = e.extra().refuri.clone();
+        let message = self.note(&mut e, "target", true, refuri.as_ref());
+        message.map(Into::into).into_iter().chain(once(e.into()))
+    }
+
+    /// Topics can only contain the message, as they are no body elements.
+    fn transform_topic(&mut self, mut e: e::Topic) -> impl Iterator<Item = e::Topic> {
+        let message = self.note(&mut e, "topic", true, None);
+        self.transform_children(&mut e, Self::transform_sub_topic);
+        e.children_mut()
+            .extend(message.map(|msg| c::BodyElement::from(msg).into()));
+        once(e)
+    }
+
+    note_in! {
+        transform_section(Section, "section", false) -> c::SubStructure, transform_structural_sub_element;
+        transform_sidebar(Sidebar, "sidebar", true) -> c::SubStructure, transform_sub_sidebar;
+        transform_citation(Citation, "citation", true) -> c::BodyElement, transform_sub_footnote;
+        transform_table(Table, "table", true) -> c::BodyElement, transform_sub_table;
+        transform_image(Image, "image", true) -> c::BodyElement;
+        transform_math_block(MathBlock, "math-block", true) -> c::BodyElement;
+    }
+}
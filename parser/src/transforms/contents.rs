@@ -0,0 +1,288 @@
+/*! Generate tables of contents.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#table-of-contents>
+ *
+ * The `contents` directive creates a topic containing a [`e::Pending`] element.
+ * This transform replaces it with a nested bullet list of references to the sections
+ * of the document, or of the section containing the topic if it’s local.
+ * Depending on the `backlinks` option, section titles link back to their entry or to the topic.
+ *
+ * Port of <https://repo.or.cz/docutils.git/blob/HEAD:/docutils/docutils/transforms/parts.py>
+ */
+
+use std::collections::HashMap;
+
+use document_tree::{
+    Element, ExtraAttributes, HasChildren, attribute_types::ID, element_categories as c,
+    elements as e,
+};
+
+use super::Transform;
+
+/// Transform filling in tables of contents.
+#[derive(Debug, Default)]
+pub struct Contents {
+    /// Number of generated entries, used for their IDs
+    entries: usize,
+    /// Section ID → ID the section title links back to
+    backlinks: HashMap<ID, ID>,
+}
+
+impl Transform for Contents {
+    fn transform(&mut self, mut d: e::Document) -> e::Document {
+        let outline = Outline::from_children(d.children());
+        self.fill_contents(d.children_mut(), &outline, &outline);
+        if !self.backlinks.is_empty() {
+            self.set_backlinks(d.children_mut());
+        }
+        d
+    }
+}
+
+/// Section structure of the document
+#[derive(Debug)]
+struct Outline {
+    id: Option<ID>,
+    title: Vec<c::TextOrInlineElement>,
+    children: Vec<Outline>,
+}
+
+impl Outline {
+    fn from_children(children: &[c::StructuralSubElement]) -> Vec<Outline> {
+        children
+            .iter()
+            .filter_map(as_section)
+            .map(|section| Outline {
+                id: section.ids().first().cloned(),
+                title: match section.children().first() {
+                    Some(c::StructuralSubElement::Title(t)) => filter_title(t.children()),
+                    _ => vec![],
+                },
+                children: Outline::from_children(section.children()),
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Backlinks {
+    Entry,
+    Top,
+    None,
+}
+
+impl Contents {
+    /// Fills in the tables of contents among `children` and their subsections.
+    /// `local` is the outline of the section (or document) containing the `children`.
+    fn fill_contents(
+        &mut self,
+        children: &mut Vec<c::StructuralSubElement>,
+        local: &[Outline],
+        document: &[Outline],
+    ) {
+        let mut idx = 0;
+        while idx < children.len() {
+            if let c::StructuralSubElement::SubStructure(sub) = &mut children[idx] {
+                match sub.as_mut() {
+                    c::SubStructure::Topic(topic) if find_pending(topic).is_some() => {
+                        let outline = if topic.classes().iter().any(|c| c == "local") {
+                            local
+                        } else {
+                            document
+                        };
+                        if !self.fill_topic(topic, outline) {
+                            // empty tables of contents are removed
+                            children.remove(idx);
+                            continue;
+                        }
+                    }
+                    c::SubStructure::Section(section) => {
+                        let id = section.ids().first().cloned();
+                        let sub_local = local
+                            .iter()
+                            .find(|o| o.id == id)
+                            .map_or(&[][..], |o| &o.children);
+                        self.fill_contents(section.children_mut(), sub_local, document);
+                    }
+                    _ => {}
+                }
+            }
+            idx += 1;
+        }
+    }
+
+    /// Replaces the pending element in a topic. Returns false if the table of contents is empty.
+    fn fill_topic(&mut self, topic: &mut e::Topic, outline: &[Outline]) -> bool {
+        let pending_idx = find_pending(topic).expect("Checked by caller");
+        let c::SubTopic::BodyElement(b) = topic.children_mut().remove(pending_idx) else {
+            unreachable!()
+        };
+        let c::BodyElement::Pending(pending) = *b else {
+            unreachable!()
+        };
+        let mut depth = usize::MAX;
+        let mut backlinks = Backlinks::Entry;
+        for (name, value) in &pending.extra().details {
+            match (name.0.as_str(), value.as_str()) {
+                ("depth", depth_str) => depth = depth_str.parse().unwrap_or(usize::MAX),
+                ("backlinks", "top") => backlinks = Backlinks::Top,
+                ("backlinks", "none") => backlinks = Backlinks::None,
+                _ => {}
+            }
+        }
+        let topic_id = topic.ids().first().cloned();
+        let Some(list) = self.build_list(outline, depth, backlinks, topic_id.as_ref()) else {
+            return false;
+        };
+        topic.children_mut().insert(pending_idx, list.into());
+        true
+    }
+
+    fn build_list(
+        &mut self,
+        outline: &[Outline],
+        depth: usize,
+        backlinks: Backlinks,
+        topic_id: Option<&ID>,
+    ) -> Option<e::BulletList> {
+        if depth == 0 {
+            return None;
+        }
+        let mut items = vec![];
+        for section in outline {
+            let Some(section_id) = &section.id else {
+                continue;
+            };
+            self.entries += 1;
+            let entry_id = ID(format!("toc-entry-{}", self.entries));
+            let mut reference = e::Reference::with_children(section.title.clone());
+            reference.ids_mut().push(entry_id.clone());
+            reference.extra_mut().refid = Some(section_id.clone());
+            let backlink = match backlinks {
+                Backlinks::Entry => Some(entry_id),
+                Backlinks::Top => topic_id.cloned(),
+                Backlinks::None => None,
+            };
+            if let Some(backlink) = backlink {
+                self.backlinks.insert(section_id.clone(), backlink);
+            }
+            let mut item: Vec<c::BodyElement> =
+                vec![e::Paragraph::with_children(vec![reference.into()]).into()];
+            if let Some(sublist) =
+                self.build_list(&section.children, depth - 1, backlinks, topic_id)
+            {
+                item.push(sublist.into());
+            }
+            items.push(e::ListItem::with_children(item));
+        }
+        if items.is_empty() {
+            return None;
+        }
+        let mut list = e::BulletList::with_children(items);
+        list.extra_mut().bullet = Some("-".to_owned());
+        Some(list)
+    }
+
+    /// Makes section titles link back to the table of contents.
+    fn set_backlinks(&self, children: &mut [c::StructuralSubElement]) {
+        for child in children {
+            let c::StructuralSubElement::SubStructure(sub) = child else {
+                continue;
+            };
+            let c::SubStructure::Section(section) = sub.as_mut() else {
+                continue;
+            };
+            let backlink = section
+                .ids()
+                .first()
+                .and_then(|id| self.backlinks.get(id))
+                .cloned();
+            if let Some(c::StructuralSubElement::Title(title)) = section.children_mut().first_mut()
+            {
+                title.extra_mut().refid = backlink;
+            }
+            self.set_backlinks(section.children_mut());
+        }
+    }
+}
+
+fn find_pending(topic: &e::Topic) -> Option<usize> {
+    topic.children().iter().position(|c| {
+        let c::SubTopic::BodyElement(b) = c else {
+            return false;
+        };
+        let c::BodyElement::Pending(p) = b.as_ref() else {
+            return false;
+        };
+        p.extra()
+            .transform
+            .as_ref()
+            .is_some_and(|t| t.0 == "contents")
+    })
+}
+
+fn as_section(elem: &c::StructuralSubElement) -> Option<&e::Section> {
+    match elem {
+        c::StructuralSubElement::SubStructure(sub) => match sub.as_ref() {
+            c::SubStructure::Section(section) => Some(section),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Copies a title for an entry, without references, footnote references, and targets,
+/// also when nested in other inline elements.
+pub(super) fn filter_title(title: &[c::TextOrInlineElement]) -> Vec<c::TextOrInlineElement> {
+    title.iter().flat_map(filter_inline).collect()
+}
+
+fn filter_inline(elem: &c::TextOrInlineElement) -> Vec<c::TextOrInlineElement> {
+    use c::TextOrInlineElement as T;
+
+    /// Copies the elements of the given types with filtered children.
+    macro_rules! filter_nested {
+        ($($variant:ident),+) => {
+            match elem {
+                $(T::$variant(e) => {
+                    let mut e = e.as_ref().clone();
+                    let children = filter_title(e.children());
+                    *e.children_mut() = children;
+                    vec![e.into()]
+                })+
+                T::Reference(r) => filter_title(r.children()),
+                T::TargetInline(t) => t.children().iter().map(|s| s.as_str().into()).collect(),
+                T::FootnoteReference(_) | T::CitationReference(_) | T::ImageInline(_) => vec![],
+                elem => vec![elem.clone()],
+            }
+        };
+    }
+    filter_nested!(
+        Emphasis,
+        Strong,
+        TitleReference,
+        Abbreviation,
+        Acronym,
+        Superscript,
+        Subscript,
+        Inline
+    )
+}
+
+/// Returns whether a title contains a reference, also when nested in other inline elements.
+pub(super) fn contains_reference(title: &[c::TextOrInlineElement]) -> bool {
+    use c::TextOrInlineElement as T;
+
+    title.iter().any(|elem| match elem {
+        T::Reference(_) => true,
+        T::Emphasis(e) => contains_reference(e.children()),
+        T::Strong(e) => contains_reference(e.children()),
+        T::TitleReference(e) => contains_reference(e.children()),
+        T::Abbreviation(e) => contains_reference(e.children()),
+        T::Acronym(e) => contains_reference(e.children()),
+        T::Superscript(e) => contains_reference(e.children()),
+        T::Subscript(e) => contains_reference(e.children()),
+        T::Inline(e) => contains_reference(e.children()),
+        _ => false,
+    })
+}
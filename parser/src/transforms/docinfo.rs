@@ -0,0 +1,231 @@
+/*! Extract bibliographic fields.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/restructuredtext.html#bibliographic-fields>
+ *
+ * A field list that is the first element of the document
+ * (only preceded by the title, comments, targets, …) becomes an [`e::Docinfo`] element.
+ * Registered field names are turned into bibliographic elements,
+ * except for dedication and abstract, which become topics after the docinfo.
+ * Other fields are kept as generic fields.
+ *
+ * Since docutils promotes the title of a lone top-level section to the document title
+ * (and the one of a lone subsection to the subtitle), fields following those titles are also extracted.
+ *
+ * Port of <https://repo.or.cz/docutils.git/blob/HEAD:/docutils/docutils/transforms/frontmatter.py>
+ */
+
+use std::mem::take;
+
+use document_tree::{
+    HasChildren, element_categories as c,
+    elements::{self as e, Element},
+};
+
+use super::Transform;
+use crate::conversion::whitespace_normalize_name;
+
+/// Transform converting the first field list of a document into [`e::Docinfo`].
+#[derive(Debug, Default)]
+pub struct DocInfo;
+
+impl Transform for DocInfo {
+    fn transform(&mut self, mut d: e::Document) -> e::Document {
+        // title and subtitle
+        insert_docinfo(d.children_mut(), 2);
+        d
+    }
+}
+
+fn insert_docinfo(children: &mut Vec<c::StructuralSubElement>, promotable_sections: usize) {
+    let Some(idx) = children.iter().position(|c| !is_pre_bibliographic(c)) else {
+        return;
+    };
+    let is_last = idx + 1 == children.len();
+    let c::StructuralSubElement::SubStructure(sub) = &mut children[idx] else {
+        return;
+    };
+    match sub.as_mut() {
+        c::SubStructure::Section(section) if promotable_sections > 0 && is_last => {
+            insert_docinfo(section.children_mut(), promotable_sections - 1);
+        }
+        c::SubStructure::BodyElement(b) if matches!(b.as_ref(), c::BodyElement::FieldList(_)) => {
+            let c::BodyElement::FieldList(field_list) = b.as_mut() else {
+                unreachable!()
+            };
+            let (docinfo, topics) = extract_bibliographic(take(field_list.children_mut()));
+            children.splice(
+                idx..=idx,
+                std::iter::once(docinfo.into())
+                    .chain(topics.into_iter().map(|t| c::SubStructure::from(t).into())),
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Elements that can occur before the docinfo. Equivalent to docutils’ `PreBibliographic`.
+fn is_pre_bibliographic(elem: &c::StructuralSubElement) -> bool {
+    use c::BodyElement as B;
+    use c::StructuralSubElement as S;
+
+    match elem {
+        S::Title(_) | S::Subtitle(_) | S::Decoration(_) => true,
+        S::Docinfo(_) => false,
+        S::SubStructure(sub) => match sub.as_ref() {
+            c::SubStructure::BodyElement(b) => matches!(
+                b.as_ref(),
+                B::Comment(_)
+                    | B::SubstitutionDefinition(_)
+                    | B::Target(_)
+                    | B::Pending(_)
+                    | B::SystemMessage(_)
+                    | B::Raw(_)
+            ),
+            _ => false,
+        },
+    }
+}
+
+fn extract_bibliographic(fields: Vec<e::Field>) -> (e::Docinfo, Vec<e::Topic>) {
+    let mut docinfo = e::Docinfo::default();
+    let mut topics = vec![];
+    for field in fields {
+        let Some(name) = field_name(&field) else {
+            docinfo.append_child(field);
+            continue;
+        };
+        let normalized = name.to_lowercase();
+        let body = field_body(&field).children().clone();
+        match convert_field(&normalized, body) {
+            Some(Bibliographic::Element(elem)) => docinfo.append_child(elem),
+            Some(Bibliographic::Topic(topic)) => topics.push(topic),
+            None => {
+                let mut field = field;
+                field.classes_mut().push(normalized.replace(' ', "-"));
+                docinfo.append_child(field);
+            }
+        }
+    }
+    (docinfo, topics)
+}
+
+enum Bibliographic {
+    Element(c::BibliographicElement),
+    Topic(e::Topic),
+}
+
+/// Converts a registered field. Returns `None` for unknown fields or unexpected content.
+fn convert_field(name: &str, mut body: Vec<c::BodyElement>) -> Option<Bibliographic> {
+    use Bibliographic::{Element, Topic};
+
+    Some(match name {
+        "dedication" | "abstract" => {
+            let title = if name == "dedication" {
+                "Dedication"
+            } else {
+                "Abstract"
+            };
+            let mut children: Vec<c::SubTopic> =
+                vec![e::Title::with_children(vec![title.into()]).into()];
+            children.extend(body.into_iter().map(Into::into));
+            let mut topic = e::Topic::with_children(children);
+            topic.classes_mut().push(name.to_owned());
+            Topic(topic)
+        }
+        "authors" => Element(convert_authors(body)?.into()),
+        _ => {
+            let [c::BodyElement::Paragraph(p)] = body.as_mut_slice() else {
+                return None;
+            };
+            let inlines = take(p.children_mut());
+            Element(match name {
+                "author" => e::Author::with_children(inlines).into(),
+                "organization" => e::Organization::with_children(inlines).into(),
+                "address" => e::Address::with_children(inlines).into(),
+                "contact" => e::Contact::with_children(inlines).into(),
+                "version" => e::Version::with_children(inlines).into(),
+                "revision" => e::Revision::with_children(inlines).into(),
+                "status" => e::Status::with_children(inlines).into(),
+                "date" => e::Date::with_children(inlines).into(),
+                "copyright" => e::Copyright::with_children(inlines).into(),
+                _ => return None,
+            })
+        }
+    })
+}
+
+/// Authors are either a single paragraph separated by “;” or “,”,
+/// multiple paragraphs, or a bullet list with one paragraph per item.
+fn convert_authors(body: Vec<c::BodyElement>) -> Option<e::Authors> {
+    let paragraphs: Vec<e::Paragraph> = match body.as_slice() {
+        [c::BodyElement::Paragraph(p)] => split_authors(p),
+        [c::BodyElement::BulletList(l)] => l
+            .children()
+            .iter()
+            .map(|item| match item.children().as_slice() {
+                [c::BodyElement::Paragraph(p)] => Some(p.as_ref().clone()),
+                _ => None,
+            })
+            .collect::<Option<_>>()?,
+        _ => body
+            .into_iter()
+            .map(|b| match b {
+                c::BodyElement::Paragraph(p) => Some(*p),
+                _ => None,
+            })
+            .collect::<Option<_>>()?,
+    };
+    if paragraphs.is_empty() {
+        return None;
+    }
+    Some(e::Authors::with_children(
+        paragraphs
+            .into_iter()
+            .map(|mut p| e::Author::with_children(take(p.children_mut())).into())
+            .collect(),
+    ))
+}
+
+/// Splits a paragraph of plain text into one paragraph per author.
+fn split_authors(p: &e::Paragraph) -> Vec<e::Paragraph> {
+    let text: Option<String> = p
+        .children()
+        .iter()
+        .map(|c| match c {
+            c::TextOrInlineElement::String(s) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect();
+    let Some(text) = text else {
+        return vec![p.clone()];
+    };
+    let sep = if text.contains(';') { ';' } else { ',' };
+    text.split(sep)
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| e::Paragraph::with_children(vec![a.into()]))
+        .collect()
+}
+
+/// Returns the normalized name of a field if it is plain text.
+fn field_name(field: &e::Field) -> Option<String> {
+    let Some(c::SubField::FieldName(name)) = field.children().first() else {
+        return None;
+    };
+    let text: Option<String> = name
+        .children()
+        .iter()
+        .map(|c| match c {
+            c::TextOrInlineElement::String(s) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect();
+    text.map(|t| whitespace_normalize_name(&t))
+}
+
+fn field_body(field: &e::Field) -> &e::FieldBody {
+    match field.children().get(1) {
+        Some(c::SubField::FieldBody(body)) => body,
+        _ => unreachable!("A field consists of a name and a body"),
+    }
+}
@@ -0,0 +1,200 @@
+/*! Propagate internal hyperlink targets to the elements following them.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/restructuredtext.html#internal-hyperlink-targets>
+ *
+ * A target without link, like `.. _anchor:`, points to the element after it.
+ * Its IDs are added to that element, and it refers to them by `refid`.
+ * Consecutive internal targets all point to the same element.
+ * If the next element is invisible (e.g. a comment) or a target of its own (e.g. a footnote),
+ * or if there is no next element, the target keeps its IDs.
+ * System messages, e.g. about duplicate target names, are skipped.
+ *
+ * Port of `PropagateTargets` in <https://repo.or.cz/docutils.git/blob/HEAD:/docutils/docutils/transforms/references.py>
+ */
+
+use std::{collections::HashSet, iter::once};
+
+use document_tree::{
+    AsElement, Element, ExtraAttributes, HasChildren, attribute_types::ID, element_categories as c,
+    elements as e,
+};
+
+use super::{IteratorMaker, Transform};
+
+/// Transform moving the IDs of internal [`e::Target`]s to the next element.
+#[derive(Debug, Default)]
+pub struct PropagateTargets {
+    /// IDs of internal targets waiting for the next element.
+    pending: Vec<ID>,
+    /// IDs that have been added to another element.
+    propagated: HashSet<ID>,
+    /// Number of anonymous internal targets. Only used for ID generation.
+    n_anonymous: usize,
+}
+
+/// How internal targets propagate to an element.
+#[derive(Debug, PartialEq)]
+enum Propagation {
+    /// Targets propagate through other targets.
+    Target,
+    /// Invisible elements and other targets like footnotes don’t receive IDs.
+    Blocked,
+    /// System messages are passed over.
+    Skipped,
+    /// All other elements do.
+    Element,
+}
+
+trait Propagate: AsElement {
+    fn propagation(&self) -> Propagation;
+}
+
+impl Propagate for c::BodyElement {
+    fn propagation(&self) -> Propagation {
+        use c::BodyElement as B;
+        match self {
+            B::Target(_) => Propagation::Target,
+            B::Comment(_)
+            | B::SubstitutionDefinition(_)
+            | B::Pending(_)
+            | B::Footnote(_)
+            | B::Citation(_) => Propagation::Blocked,
+            B::SystemMessage(_) => Propagation::Skipped,
+            _ => Propagation::Element,
+        }
+    }
+}
+
+macro_rules! impl_propagate_via_body_element {
+    ( $( $cat:ident ),+ ) => { $(
+        impl Propagate for c::$cat {
+            fn propagation(&self) -> Propagation {
+                match self {
+                    c::$cat::BodyElement(e) => e.propagation(),
+                    _ => Propagation::Element,
+                }
+            }
+        }
+    )+ };
+}
+impl_propagate_via_body_element!(SubTopic, SubSidebar, SubBlockQuote, SubFootnote);
+
+impl Propagate for c::SubStructure {
+    fn propagation(&self) -> Propagation {
+        match self {
+            c::SubStructure::BodyElement(e) => e.propagation(),
+            _ => Propagation::Element,
+        }
+    }
+}
+
+impl Propagate for c::StructuralSubElement {
+    fn propagation(&self) -> Propagation {
+        match self {
+            c::StructuralSubElement::SubStructure(e) => e.propagation(),
+            _ => Propagation::Element,
+        }
+    }
+}
+
+impl PropagateTargets {
+    /// Like [`Transform::transform_children`], but adds pending IDs to the children.
+    fn propagate<C, E>(&mut self, e: &mut E, mut meth: impl for<'a> IteratorMaker<&'a mut Self, C>)
+    where
+        E: HasChildren<C>,
+        C: Propagate,
+    {
+        let mut new = Vec::new();
+        for mut c in e.children_mut().drain(..) {
+            match c.propagation() {
+                Propagation::Blocked => self.pending.clear(),
+                Propagation::Skipped => {}
+                propagation => {
+                    if let Some(elem) = c.as_element_mut() {
+                        self.propagated.extend(self.pending.iter().cloned());
+                        let ids = elem.ids_mut();
+                        if propagation == Propagation::Target {
+                            // Keep the IDs of consecutive targets in document order
+                            ids.splice(0..0, self.pending.drain(..));
+                        } else {
+                            ids.append(&mut self.pending);
+                        }
+                    }
+                }
+            }
+            new.extend(meth(self, c));
+        }
+        e.children_mut().extend(new);
+    }
+}
+
+macro_rules! propagate_in {
+    ( $( $method:ident($elem:ident) -> $item:ty, $children:ident; )+ ) => { $(
+        fn $method(&mut self, mut e: e::$elem) -> impl Iterator<Item = $item> {
+            self.propagate(&mut e, Self::$children);
+            once(e.into())
+        }
+    )+ };
+}
+
+impl Transform for PropagateTargets {
+    fn transform(&mut self, mut d: e::Document) -> e::Document {
+        self.propagate(&mut d, Self::transform_structural_sub_element);
+        RemovePropagated(&self.propagated).transform(d)
+    }
+
+    fn transform_target(&mut self, mut e: e::Target) -> impl Iterator<Item = c::BodyElement> {
+        let extra = e.extra();
+        if extra.refuri.is_none() && extra.refid.is_none() && extra.refname.is_empty() {
+            if e.ids().is_empty() {
+                self.n_anonymous += 1;
+                e.ids_mut().push(ID(format!("target-{}", self.n_anonymous)));
+            }
+            // This includes the IDs of preceding targets
+            self.pending.clone_from(e.ids());
+        }
+        once(e.into())
+    }
+
+    propagate_in! {
+        transform_section(Section) -> c::SubStructure, transform_structural_sub_element;
+        transform_topic(Topic) -> e::Topic, transform_sub_topic;
+        transform_sidebar(Sidebar) -> c::SubStructure, transform_sub_sidebar;
+        transform_header(Header) -> c::DecorationElement, transform_body_element;
+        transform_footer(Footer) -> c::DecorationElement, transform_body_element;
+        transform_compound(Compound) -> c::BodyElement, transform_body_element;
+        transform_container(Container) -> c::BodyElement, transform_body_element;
+        transform_block_quote(BlockQuote) -> c::BodyElement, transform_sub_block_quote;
+        transform_admonition(Admonition) -> c::BodyElement, transform_sub_topic;
+        transform_attention(Attention) -> c::BodyElement, transform_body_element;
+        transform_hint(Hint) -> c::BodyElement, transform_body_element;
+        transform_note(Note) -> c::BodyElement, transform_body_element;
+        transform_caution(Caution) -> c::BodyElement, transform_body_element;
+        transform_danger(Danger) -> c::BodyElement, transform_body_element;
+        transform_error(Error) -> c::BodyElement, transform_body_element;
+        transform_important(Important) -> c::BodyElement, transform_body_element;
+        transform_tip(Tip) -> c::BodyElement, transform_body_element;
+        transform_warning(Warning) -> c::BodyElement, transform_body_element;
+        transform_footnote(Footnote) -> c::BodyElement, transform_sub_footnote;
+        transform_citation(Citation) -> c::BodyElement, transform_sub_footnote;
+        transform_table_entry(TableEntry) -> e::TableEntry, transform_body_element;
+        transform_list_item(ListItem) -> e::ListItem, transform_body_element;
+        transform_definition(Definition) -> c::SubDLItem, transform_body_element;
+        transform_field_body(FieldBody) -> c::SubField, transform_body_element;
+        transform_description(Description) -> c::SubOptionListItem, transform_body_element;
+        transform_legend(Legend) -> c::SubFigure, transform_body_element;
+    }
+}
+
+/// Makes internal targets refer to the element their IDs have been added to.
+struct RemovePropagated<'a>(&'a HashSet<ID>);
+
+impl Transform for RemovePropagated<'_> {
+    fn transform_target(&mut self, mut e: e::Target) -> impl Iterator<Item = c::BodyElement> {
+        if !e.ids().is_empty() && e.ids().iter().all(|id| self.0.contains(id)) {
+            let ids = std::mem::take(e.ids_mut());
+            e.extra_mut().refid = ids.into_iter().next();
+        }
+        once(e.into())
+    }
+}
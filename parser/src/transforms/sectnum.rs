@@ -0,0 +1,133 @@
+/*! Number sections automatically.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/directives.html#automatic-section-numbering>
+ *
+ * The `sectnum` directive leaves a [`e::Pending`] element in the tree.
+ * If there is one, this transform removes it and prefixes section titles
+ * with [`e::Generated`] numbers like “1.2”, up to the configured depth.
+ * It has to run before [`super::Contents`] so the table of contents includes the numbers.
+ *
+ * Port of <https://repo.or.cz/docutils.git/blob/HEAD:/docutils/docutils/transforms/parts.py>
+ */
+
+use std::iter::once;
+
+use document_tree::{
+    Element, ExtraAttributes, HasChildren, element_categories as c, elements as e,
+};
+
+use super::Transform;
+
+/// Transform numbering sections if the document contains a `sectnum` directive.
+#[derive(Debug, Default)]
+pub struct SectNum;
+
+impl Transform for SectNum {
+    fn transform(&mut self, d: e::Document) -> e::Document {
+        let mut collect = CollectSettings::default();
+        let d = collect.transform(d);
+        match collect.settings {
+            Some(settings) => Numbering::new(settings).transform(d),
+            None => d,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Settings {
+    depth: usize,
+    start: usize,
+    prefix: String,
+    suffix: String,
+}
+
+impl From<&e::Pending> for Settings {
+    fn from(pending: &e::Pending) -> Self {
+        let mut settings = Settings {
+            depth: usize::MAX,
+            start: 1,
+            prefix: String::new(),
+            suffix: String::new(),
+        };
+        for (name, value) in &pending.extra().details {
+            match name.0.as_str() {
+                "depth" => settings.depth = value.parse().unwrap_or(usize::MAX),
+                "start" => settings.start = value.parse().unwrap_or(1),
+                "prefix" => settings.prefix.clone_from(value),
+                "suffix" => settings.suffix.clone_from(value),
+                _ => {}
+            }
+        }
+        settings
+    }
+}
+
+/// Removes the pending elements and keeps the settings of the last one.
+#[derive(Debug, Default)]
+struct CollectSettings {
+    settings: Option<Settings>,
+}
+
+impl Transform for CollectSettings {
+    fn transform_pending(&mut self, e: e::Pending) -> impl Iterator<Item = c::BodyElement> {
+        let is_sectnum = e
+            .extra()
+            .transform
+            .as_ref()
+            .is_some_and(|t| t.0 == "sectnum");
+        if is_sectnum {
+            self.settings = Some(Settings::from(&e));
+        }
+        (!is_sectnum).then(|| e.into()).into_iter()
+    }
+}
+
+#[derive(Debug)]
+struct Numbering {
+    settings: Settings,
+    /// Numbers of the sections containing the current one
+    numbers: Vec<usize>,
+    /// Number of the next section on the current level
+    next: usize,
+}
+
+impl Numbering {
+    fn new(settings: Settings) -> Self {
+        let next = settings.start;
+        Self {
+            settings,
+            numbers: vec![],
+            next,
+        }
+    }
+}
+
+impl Transform for Numbering {
+    fn transform_section(&mut self, mut e: e::Section) -> impl Iterator<Item = c::SubStructure> {
+        if self.numbers.len() >= self.settings.depth {
+            return once(e.into());
+        }
+        self.numbers.push(self.next);
+        let number = self
+            .numbers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        if let Some(c::StructuralSubElement::Title(title)) = e.children_mut().first_mut() {
+            let text = format!(
+                "{}{number}{}\u{a0}\u{a0}\u{a0}",
+                self.settings.prefix, self.settings.suffix
+            );
+            let mut generated = e::Generated::with_children(vec![text.into()]);
+            generated.classes_mut().push("sectnum".to_owned());
+            title.children_mut().insert(0, generated.into());
+        }
+
+        let next = std::mem::replace(&mut self.next, 1);
+        self.transform_children(&mut e, Self::transform_structural_sub_element);
+        self.next = next + 1;
+        self.numbers.pop();
+        once(e.into())
+    }
+}
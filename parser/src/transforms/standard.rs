@@ -22,7 +22,32 @@
  * .. targetname2: https://link
  * ```
  *
- * There’s also anonymous links and targets without names.
+ * There’s also anonymous links and targets without names:
+ *
+ * ```restructuredtext
+ * See `this link`__ and `that one`__.
+ *
+ * .. __: https://link1
+ * __ https://link2
+ * ```
+ *
+ * Targets can also be indirect, i.e. refer to another target by name:
+ *
+ * ```restructuredtext
+ * .. _targetname3: targetname2_
+ * .. _`target name 4`: `targetname3`_
+ * ```
+ *
+ * References are resolved by following such chains to their final target.
+ * Unknown names and circular chains are reported.
+ *
+ * Internal targets point to the element after them, see [`super::PropagateTargets`].
+ *
+ * Section titles are implicit targets, so `` `Section Title`_ `` links to that section.
+ * Names are case insensitive. Names of several targets can’t be referenced, see [`super::AssignIds`].
+ *
+ * Anonymous references are matched to anonymous targets in document order.
+ * If their numbers differ, all anonymous references are reported as problematic.
  *
  * TODO: continue documenting how it’s done via <https://repo.or.cz/docutils.git/blob/HEAD:/docutils/docutils/transforms/references.py>
  *
@@ -35,9 +60,25 @@
  * In the source, they are split into two parts: footnote references and footnotes.
  *
  * Their order is defined by the order of the footnotes, not references.
+ *
+ * Citations
+ * ---------
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/restructuredtext.html#citations>
+ *
+ * Citations are like footnotes with a textual label, and are referenced by that label.
+ * They are also implicit hyperlink targets.
+ *
+ * Unresolvable references are replaced by problematic elements linking to a system message.
+ * Those messages are collected in a section with the class “system-messages” at the end of the document.
  */
 
-use std::{collections::HashMap, iter::once, num::NonZero, vec};
+use std::{
+    collections::{HashMap, HashSet},
+    iter::once,
+    num::NonZero,
+    vec,
+};
 
 use document_tree::{
     Document, HasChildren, LabelledFootnote as _,
@@ -49,10 +90,20 @@ use document_tree::{
 };
 use linearize::{Linearize, StaticMap};
 
-use super::{Transform, Visit};
+use super::{
+    AssignIds, Contents, DocInfo, PropagateTargets, SectNum, Transform, Transitions, Visit,
+    contents::{contains_reference, filter_title},
+};
+use crate::report::{Level, system_message};
 
 #[must_use]
 pub fn standard_transform(doc: Document) -> Document {
+    let doc = AssignIds::default().transform(doc);
+    let doc = DocInfo.transform(doc);
+    let doc = Transitions.transform(doc);
+    let doc = SectNum.transform(doc);
+    let doc = Contents::default().transform(doc);
+    let doc = PropagateTargets::default().transform(doc);
     let mut pass1 = Pass1::default();
     let doc = pass1.transform(doc);
     let mut pass2 = Pass2::from(&pass1);
@@ -60,21 +111,34 @@ pub fn standard_transform(doc: Document) -> Document {
     Pass3::from(&pass2).transform(doc)
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
+#[derive(Clone, Debug)]
 enum NamedTargetType {
-    Citation,
-    InternalLink,
+    Citation(ID),
+    InternalLink(ID),
     ExternalLink(Url),
     IndirectLink(NameToken),
-    SectionTitle,
+    SectionTitle(ID),
 }
 impl NamedTargetType {
+    /// The type of an explicit target.
+    /// Internal targets without element to refer to link to themselves.
+    fn of_target(e: &e::Target) -> Option<Self> {
+        let extra = e.extra();
+        if let Some(uri) = &extra.refuri {
+            Some(NamedTargetType::ExternalLink(uri.clone()))
+        } else if let Some(name) = extra.refname.first() {
+            Some(NamedTargetType::IndirectLink(name.clone()))
+        } else {
+            let id = extra.refid.as_ref().or(e.ids().first())?;
+            Some(NamedTargetType::InternalLink(id.clone()))
+        }
+    }
+
     #[allow(dead_code)]
     /// See <https://docutils.sourceforge.io/docs/ref/rst/restructuredtext.html#implicit-hyperlink-targets>
     fn is_implicit_target(&self) -> bool {
         use NamedTargetType as T;
-        matches!(self, T::SectionTitle | T::Citation)
+        matches!(self, T::SectionTitle(_) | T::Citation(_))
     }
 }
 
@@ -96,6 +160,10 @@ struct Pass1 {
     n_anon_footnotes: usize,
     /// Number of encountered footnote references. Only used for ID generation.
     n_footnote_refs: usize,
+    /// IDs of citations by name.
+    citations: HashMap<NameToken, ID>,
+    /// Number of encountered citation references. Only used for ID generation.
+    n_citation_refs: usize,
 }
 impl Pass1 {
     /// Get next footnote number for a type.
@@ -201,6 +269,25 @@ impl Transform for Pass1 {
         self.transform_children(&mut e, Self::transform_text_or_inline_element);
         once(e.into())
     }
+    /// Collect the IDs of citations.
+    fn transform_citation(&mut self, mut e: e::Citation) -> impl Iterator<Item = c::BodyElement> {
+        if let (Some(name), Some(id)) = (e.names().first(), e.ids().first()) {
+            self.citations.insert(name.clone(), id.clone());
+        }
+        self.transform_children(&mut e, Self::transform_sub_footnote);
+        once(e.into())
+    }
+    /// Give each citation reference an ID.
+    fn transform_citation_reference(
+        &mut self,
+        mut e: e::CitationReference,
+    ) -> impl Iterator<Item = c::TextOrInlineElement> {
+        self.n_citation_refs += 1;
+        e.ids_mut()
+            .push(ID(format!("citation-reference-{}", self.n_citation_refs)));
+        self.transform_children(&mut e, Self::transform_text_or_inline_element);
+        once(e.into())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -217,6 +304,10 @@ struct Substitution {
 struct Pass2<'p1> {
     pass1: &'p1 Pass1,
     named_targets: HashMap<NameToken, NamedTargetType>,
+    /// Names of several targets, which can’t be referenced.
+    duplicate_names: HashSet<NameToken>,
+    /// External and indirect targets by ID, including IDs of internal targets preceding them.
+    link_targets: HashMap<ID, NamedTargetType>,
     substitutions: HashMap<NameToken, Substitution>,
     normalized_substitutions: HashMap<String, Substitution>,
     /// Footnote references.
@@ -226,17 +317,28 @@ struct Pass2<'p1> {
     n_symbol_footnote_refs: usize,
     /// Number of auto-numbered footnote references.
     n_auto_num_footnote_refs: StaticMap<AutoNumberedFootnoteType, usize>,
+    /// IDs of citation references by the name they refer to, in document order.
+    citation_refs: HashMap<NameToken, Vec<ID>>,
+    /// Anonymous targets, in document order.
+    anonymous_targets: Vec<Option<NamedTargetType>>,
+    /// Number of anonymous references.
+    n_anonymous_refs: usize,
 }
 impl<'p1> From<&'p1 Pass1> for Pass2<'p1> {
     fn from(pass1: &'p1 Pass1) -> Self {
         Self {
             pass1,
             named_targets: HashMap::new(),
+            duplicate_names: HashSet::new(),
+            link_targets: HashMap::new(),
             substitutions: HashMap::new(),
             normalized_substitutions: HashMap::new(),
             footnote_refs: StaticMap::default(),
             n_symbol_footnote_refs: 0,
             n_auto_num_footnote_refs: StaticMap::default(),
+            citation_refs: HashMap::new(),
+            anonymous_targets: vec![],
+            n_anonymous_refs: 0,
         }
     }
 }
@@ -244,8 +346,10 @@ impl<'p1> From<&'p1 Pass1> for Pass2<'p1> {
 /// Pass 2.
 ///
 /// - Populate substitution definitions.
-/// - Populate (link) targets.
+/// - Populate (link) targets, including section titles.
 /// - Resolve which footnotes are referenced by footnote references.
+/// - Collect citation references.
+/// - Collect anonymous targets and count anonymous references.
 impl<'tree> Visit<'tree> for Pass2<'_> {
     fn visit_substitution_definition(&mut self, e: &'tree e::SubstitutionDefinition) {
         let subst = Substitution {
@@ -263,15 +367,34 @@ impl<'tree> Visit<'tree> for Pass2<'_> {
                 .insert(name.0.to_lowercase(), subst.clone());
         }
     }
-    fn visit_target(&mut self, e: &'tree e::Target) {
-        if let Some(uri) = &e.extra().refuri {
+    fn visit_section(&mut self, e: &'tree e::Section) {
+        if let Some(id) = e.ids().first() {
             for name in e.names() {
                 self.named_targets
-                    .insert(name.clone(), NamedTargetType::ExternalLink(uri.clone()));
+                    .insert(name.clone(), NamedTargetType::SectionTitle(id.clone()));
+            }
+        }
+        self.duplicate_names.extend(e.dupnames().iter().cloned());
+        for c in e.children() {
+            self.visit_structural_sub_element(c);
+        }
+    }
+    fn visit_target(&mut self, e: &'tree e::Target) {
+        self.duplicate_names.extend(e.dupnames().iter().cloned());
+        if e.extra().anonymous {
+            self.anonymous_targets.push(NamedTargetType::of_target(e));
+        }
+        // Indirect targets are only followed when resolving references in pass 3
+        if let Some(target) = NamedTargetType::of_target(e) {
+            for name in e.names() {
+                self.named_targets.insert(name.clone(), target.clone());
+            }
+            if !matches!(target, NamedTargetType::InternalLink(_)) {
+                for id in e.ids() {
+                    self.link_targets.insert(id.clone(), target.clone());
+                }
             }
         }
-        // TODO: as is, people can only refer to the target directly containing the URL.
-        // add refid and refnames to some HashMap and follow those later.
     }
     fn visit_footnote_reference(&mut self, e: &'tree e::FootnoteReference) {
         let id = e.ids().first().unwrap();
@@ -294,22 +417,182 @@ impl<'tree> Visit<'tree> for Pass2<'_> {
             self.visit_text_or_inline_element(c);
         }
     }
+    fn visit_reference(&mut self, e: &'tree e::Reference) {
+        if e.extra().anonymous {
+            self.n_anonymous_refs += 1;
+        }
+        for c in e.children() {
+            self.visit_text_or_inline_element(c);
+        }
+    }
+    fn visit_citation(&mut self, e: &'tree e::Citation) {
+        self.duplicate_names.extend(e.dupnames().iter().cloned());
+        if let Some(id) = e.ids().first() {
+            for name in e.names() {
+                self.named_targets
+                    .insert(name.clone(), NamedTargetType::Citation(id.clone()));
+            }
+        }
+        for c in e.children() {
+            self.visit_sub_footnote(c);
+        }
+    }
+    fn visit_citation_reference(&mut self, e: &'tree e::CitationReference) {
+        if let (Some(name), Some(id)) = (e.extra().refname.first(), e.ids().first()) {
+            self.citation_refs
+                .entry(name.clone())
+                .or_default()
+                .push(id.clone());
+        }
+    }
 }
 
+/// The final target of a hyperlink reference.
 #[derive(Debug)]
-struct Pass3<'p2>(&'p2 Pass2<'p2>);
+enum ResolvedTarget {
+    Uri(Url),
+    Id(ID),
+}
+
+/// Returns the source of a reference, e.g. to report it as problematic.
+/// References without one, like ones created by transforms, are reconstructed from their name.
+fn reference_source(e: &e::Reference) -> String {
+    if let Some(source) = &e.extra().rawsource {
+        return source.clone();
+    }
+    let name = e.extra().name.as_ref().map_or("", |n| n.0.as_str());
+    let suffix = if e.extra().anonymous { "__" } else { "_" };
+    if name.chars().all(char::is_alphanumeric) {
+        format!("{name}{suffix}")
+    } else {
+        format!("`{name}`{suffix}")
+    }
+}
+
+#[derive(Debug)]
+struct Pass3<'p2> {
+    pass2: &'p2 Pass2<'p2>,
+    /// Problems with references, added to the end of the document.
+    messages: Vec<e::SystemMessage>,
+    /// Number of problematic elements. Only used for ID generation.
+    n_problematic: usize,
+    /// Number of resolved anonymous references.
+    n_anonymous_refs: usize,
+    /// Index of the message about mismatched anonymous references and targets.
+    anonymous_mismatch: Option<usize>,
+}
 impl<'p2> Pass3<'p2> {
-    fn target_url<'t>(self: &'t Pass3<'p2>, refname: &[NameToken]) -> Option<&'t Url> {
-        // TODO: Check if the target would expand circularly
-        assert!(
-            refname.len() == 1,
-            "Expected exactly one name in a reference."
-        );
-        let name = refname[0].clone();
-        match self.0.named_targets.get(&name)? {
-            NamedTargetType::ExternalLink(url) => Some(url),
-            _ => unimplemented!(),
+    /// Follows a chain of indirect targets to its final target.
+    ///
+    /// # Errors
+    /// Returns a message if a name in the chain is unknown or the chain is circular.
+    fn resolve(&self, target: &NamedTargetType) -> Result<ResolvedTarget, String> {
+        let mut chain: Vec<&NameToken> = vec![];
+        let mut target = target;
+        loop {
+            match target {
+                NamedTargetType::ExternalLink(url) => return Ok(ResolvedTarget::Uri(url.clone())),
+                NamedTargetType::Citation(id) | NamedTargetType::SectionTitle(id) => {
+                    return Ok(ResolvedTarget::Id(id.clone()));
+                }
+                NamedTargetType::IndirectLink(name) => {
+                    let circular = chain.contains(&name);
+                    chain.push(name);
+                    if circular {
+                        let chain: Vec<_> = chain.iter().map(|n| format!("“{}”", n.0)).collect();
+                        return Err(format!(
+                            "Circular indirect hyperlink targets: {}.",
+                            chain.join(" → ")
+                        ));
+                    }
+                    target = self.pass2.named_targets.get(name).ok_or_else(|| {
+                        if self.pass2.duplicate_names.contains(name) {
+                            format!(
+                                "Duplicate target name, cannot be used as a unique reference: “{}”.",
+                                name.0
+                            )
+                        } else {
+                            format!("Unknown target name: “{}”.", name.0)
+                        }
+                    })?;
+                }
+                // Internal targets followed by another target are chained to it
+                NamedTargetType::InternalLink(id) => match self.pass2.link_targets.get(id) {
+                    Some(next) => target = next,
+                    None => return Ok(ResolvedTarget::Id(id.clone())),
+                },
+            }
+        }
+    }
+
+    /// Links a reference to its resolved target, or replaces it by a problematic element.
+    fn link(
+        &mut self,
+        mut e: e::Reference,
+        target: Result<ResolvedTarget, String>,
+    ) -> c::TextOrInlineElement {
+        match target {
+            Ok(ResolvedTarget::Uri(uri)) => e.extra_mut().refuri = Some(uri),
+            Ok(ResolvedTarget::Id(id)) => e.extra_mut().refid = Some(id),
+            Err(message) => {
+                let message = self.report(&message);
+                return self.problematic(reference_source(&e), message).into();
+            }
         }
+        e.into()
+    }
+
+    /// Adds a system message to the end of the document, returning its index.
+    fn report(&mut self, message: &str) -> usize {
+        let mut msg = system_message(Level::Error, message, None);
+        msg.ids_mut()
+            .push(ID(format!("system-message-{}", self.messages.len() + 1)));
+        self.messages.push(msg);
+        self.messages.len() - 1
+    }
+
+    /// Replaces an unresolvable reference by a problematic element linking to a message.
+    fn problematic(&mut self, text: String, message: usize) -> e::Problematic {
+        self.n_problematic += 1;
+        let id = ID(format!("problematic-{}", self.n_problematic));
+        let msg = &mut self.messages[message];
+        msg.extra_mut().backrefs.push(id.clone());
+
+        let mut problematic = e::Problematic::with_children(vec![text.into()]);
+        problematic.ids_mut().push(id);
+        problematic.extra_mut().refid = msg.ids().first().cloned();
+        problematic
+    }
+
+    /// Matches an anonymous reference to the anonymous target at the same position.
+    fn resolve_anonymous(&mut self, e: e::Reference) -> c::TextOrInlineElement {
+        let targets = &self.pass2.anonymous_targets;
+        if self.pass2.n_anonymous_refs == targets.len() {
+            let target = targets[self.n_anonymous_refs].as_ref();
+            self.n_anonymous_refs += 1;
+            return match target {
+                Some(target) => {
+                    let target = self.resolve(target);
+                    self.link(e, target)
+                }
+                None => e.into(),
+            };
+        }
+        let message = if let Some(message) = self.anonymous_mismatch {
+            message
+        } else {
+            let message = self.report(&format!(
+                "Anonymous hyperlink mismatch: {} references but {} targets.",
+                self.pass2.n_anonymous_refs,
+                targets.len(),
+            ));
+            *self.anonymous_mismatch.insert(message)
+        };
+        self.problematic(reference_source(&e), message).into()
+    }
+
+    fn citation_id<'t>(self: &'t Pass3<'p2>, refname: &[NameToken]) -> Option<&'t ID> {
+        self.pass2.pass1.citations.get(refname.first()?)
     }
 
     fn substitution<'t>(self: &'t Pass3<'p2>, refname: &[NameToken]) -> Option<&'t Substitution> {
@@ -319,21 +602,54 @@ impl<'p2> Pass3<'p2> {
             "Expected exactly one name in a substitution reference."
         );
         let name = refname[0].clone();
-        self.0
-            .substitutions
-            .get(&name)
-            .or_else(|| self.0.normalized_substitutions.get(&name.0.to_lowercase()))
+        self.pass2.substitutions.get(&name).or_else(|| {
+            self.pass2
+                .normalized_substitutions
+                .get(&name.0.to_lowercase())
+        })
     }
 }
 
 impl<'p2> From<&'p2 Pass2<'p2>> for Pass3<'p2> {
-    fn from(p: &'p2 Pass2<'p2>) -> Self {
-        Pass3(p)
+    fn from(pass2: &'p2 Pass2<'p2>) -> Self {
+        Pass3 {
+            pass2,
+            messages: vec![],
+            n_problematic: 0,
+            n_anonymous_refs: 0,
+            anonymous_mismatch: None,
+        }
     }
 }
 
 /// 3rd pass.
 impl Transform for Pass3<'_> {
+    fn transform(&mut self, mut d: e::Document) -> e::Document {
+        self.transform_children(&mut d, Self::transform_structural_sub_element);
+        if !self.messages.is_empty() {
+            let title = e::Title::with_children(vec!["Docutils System Messages".into()]);
+            let mut section = e::Section::with_children(vec![title.into()]);
+            section
+                .ids_mut()
+                .push(ID("docutils-system-messages".to_owned()));
+            section.classes_mut().push("system-messages".to_owned());
+            section.children_mut().extend(
+                self.messages
+                    .drain(..)
+                    .map(|msg| c::BodyElement::from(msg).into()),
+            );
+            d.children_mut().push(section.into());
+        }
+        d
+    }
+    /// Titles containing references can’t link back to the table of contents.
+    fn transform_title(&mut self, mut e: e::Title) -> impl Iterator<Item = e::Title> {
+        self.transform_children(&mut e, Self::transform_text_or_inline_element);
+        if contains_reference(e.children()) {
+            e.extra_mut().refid = None;
+        }
+        once(e)
+    }
     fn transform_substitution_definition(
         &mut self,
         _: e::SubstitutionDefinition,
@@ -380,10 +696,34 @@ impl Transform for Pass3<'_> {
         &mut self,
         mut e: e::Reference,
     ) -> impl Iterator<Item = c::TextOrInlineElement> {
-        if e.extra().refuri.is_none()
-            && let Some(uri) = self.target_url(&e.extra().refname)
-        {
-            e.extra_mut().refuri = Some(uri.clone());
+        if e.extra().anonymous {
+            return once(self.resolve_anonymous(e));
+        }
+        // References with a refid (e.g. in tables of contents) are already resolved,
+        // but can contain substitutions, which must not add nested references to entries
+        if e.extra().refuri.is_some() || e.extra().refid.is_some() {
+            self.transform_children(&mut e, Self::transform_text_or_inline_element);
+            if e.extra().refid.is_some() {
+                let children = filter_title(e.children());
+                *e.children_mut() = children;
+            }
+            return once(e.into());
+        }
+        let Some(name) = e.extra().refname.first() else {
+            return once(e.into());
+        };
+        let target = self.resolve(&NamedTargetType::IndirectLink(name.clone()));
+        once(self.link(e, target))
+    }
+    fn transform_target(&mut self, mut e: e::Target) -> impl Iterator<Item = c::BodyElement> {
+        // Indirect targets link to the final target of their chain.
+        // Problems are reported at the references using them.
+        if let Some(name) = e.extra().refname.first() {
+            match self.resolve(&NamedTargetType::IndirectLink(name.clone())) {
+                Ok(ResolvedTarget::Uri(uri)) => e.extra_mut().refuri = Some(uri),
+                Ok(ResolvedTarget::Id(id)) => e.extra_mut().refid = Some(id),
+                Err(_) => {}
+            }
         }
         once(e.into())
     }
@@ -393,7 +733,9 @@ impl Transform for Pass3<'_> {
         2. (in resolve_refs) set `footnote_reference[refid]`s, `footnote[backref]`s and `footnote>label`
         */
         let id = e.ids().first().unwrap();
-        let num = self.0.pass1.footnotes[e.footnote_type()].get(id).unwrap();
+        let num = self.pass2.pass1.footnotes[e.footnote_type()]
+            .get(id)
+            .unwrap();
         if e.get_label().is_err() {
             e.children_mut().insert(
                 0,
@@ -402,7 +744,7 @@ impl Transform for Pass3<'_> {
         }
 
         // backrefs
-        e.extra_mut().backrefs = self.0.footnote_refs[e.footnote_type()]
+        e.extra_mut().backrefs = self.pass2.footnote_refs[e.footnote_type()]
             .iter()
             .filter(|&(_, num2)| num == num2)
             .map(|(refid, _)| refid.clone())
@@ -419,10 +761,12 @@ impl Transform for Pass3<'_> {
         // TODO: dedupe
         // https://docutils.sourceforge.io/docs/ref/doctree.html#footnote-reference
         let refid = e.ids().first().unwrap();
-        let n = self.0.footnote_refs[e.footnote_type()].get(refid).unwrap();
+        let n = self.pass2.footnote_refs[e.footnote_type()]
+            .get(refid)
+            .unwrap();
 
         // get referenced footnote ID
-        e.extra_mut().refid = self.0.pass1.footnotes[e.footnote_type()]
+        e.extra_mut().refid = self.pass2.pass1.footnotes[e.footnote_type()]
             .iter()
             .find_map(|(k, v)| (v == n).then_some(k.clone()));
 
@@ -435,4 +779,32 @@ impl Transform for Pass3<'_> {
         self.transform_children(&mut e, Self::transform_text_or_inline_element);
         once(e.into())
     }
+    fn transform_citation(&mut self, mut e: e::Citation) -> impl Iterator<Item = c::BodyElement> {
+        if let Some(refs) = e
+            .names()
+            .first()
+            .and_then(|name| self.pass2.citation_refs.get(name))
+        {
+            e.extra_mut().backrefs.clone_from(refs);
+        }
+        self.transform_children(&mut e, Self::transform_sub_footnote);
+        once(e.into())
+    }
+    fn transform_citation_reference(
+        &mut self,
+        mut e: e::CitationReference,
+    ) -> impl Iterator<Item = c::TextOrInlineElement> {
+        if let Some(id) = self.citation_id(&e.extra().refname).cloned() {
+            e.extra_mut().refid = Some(id);
+            return once(e.into());
+        }
+        let label = e.extra().refname.first().map_or("", |n| n.0.as_str());
+        let message = self.report(&format!("Unknown target name: “{label}”."));
+        let text = match e.children().first() {
+            Some(c::TextOrInlineElement::String(text)) => text.as_str(),
+            _ => label,
+        };
+        let text = format!("[{text}]_");
+        once(self.problematic(text, message).into())
+    }
 }
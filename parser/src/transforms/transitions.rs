@@ -0,0 +1,103 @@
+/*! Validate the placement of transitions.
+ *
+ * See <https://docutils.sourceforge.io/docs/ref/rst/restructuredtext.html#transitions>
+ *
+ * A transition may not begin or end a section or document,
+ * nor may two transitions be immediately adjacent.
+ * Transitions at the start of a section or document and adjacent transitions are reported as errors.
+ * A transition at the end of a section is moved after the section,
+ * a transition at the end of the document is reported as an error.
+ *
+ * Port of <https://repo.or.cz/docutils.git/blob/HEAD:/docutils/docutils/transforms/misc.py>
+ */
+
+use document_tree::{HasChildren, element_categories as c, elements as e};
+
+use super::Transform;
+use crate::report::{Level, system_message};
+
+/// Transform checking and fixing the placement of [`e::Transition`] elements.
+#[derive(Debug, Default)]
+pub struct Transitions;
+
+impl Transform for Transitions {
+    fn transform(&mut self, mut d: e::Document) -> e::Document {
+        let children = d.children_mut();
+        if let Some(transition) = check_transitions(children) {
+            children.push(transition);
+            children.push(error("Document may not end with a transition.").into());
+        }
+        d
+    }
+}
+
+/// Checks the transitions among `children`, recursing into sections.
+/// Returns a trailing transition, which has to be moved after the parent.
+fn check_transitions(
+    children: &mut Vec<c::StructuralSubElement>,
+) -> Option<c::StructuralSubElement> {
+    // Move transitions at the end of subsections after those subsections.
+    let mut idx = 0;
+    while idx < children.len() {
+        if let Some(section) = as_section(&mut children[idx])
+            && let Some(transition) = check_transitions(section.children_mut())
+        {
+            children.insert(idx + 1, transition);
+        }
+        idx += 1;
+    }
+
+    let start = children
+        .iter()
+        .position(|c| {
+            !matches!(
+                c,
+                c::StructuralSubElement::Title(_) | c::StructuralSubElement::Subtitle(_)
+            )
+        })
+        .unwrap_or(children.len());
+    let mut idx = start;
+    while idx < children.len() {
+        if is_transition(&children[idx]) {
+            if idx == start {
+                let msg = error("Document or section may not begin with a transition.");
+                children.insert(idx, msg.into());
+                idx += 1;
+            } else if is_transition(&children[idx - 1]) {
+                let msg = error(
+                    "At least one body element must separate transitions; adjacent transitions are not allowed.",
+                );
+                children.insert(idx, msg.into());
+                idx += 1;
+            }
+        }
+        idx += 1;
+    }
+
+    if children.last().is_some_and(is_transition) {
+        children.pop()
+    } else {
+        None
+    }
+}
+
+fn error(message: &str) -> c::SubStructure {
+    c::BodyElement::from(system_message(Level::Error, message, None)).into()
+}
+
+fn as_section(elem: &mut c::StructuralSubElement) -> Option<&mut e::Section> {
+    match elem {
+        c::StructuralSubElement::SubStructure(sub) => match sub.as_mut() {
+            c::SubStructure::Section(section) => Some(section),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_transition(elem: &c::StructuralSubElement) -> bool {
+    matches!(
+        elem,
+        c::StructuralSubElement::SubStructure(sub) if matches!(sub.as_ref(), c::SubStructure::Transition(_))
+    )
+}
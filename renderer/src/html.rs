@@ -1,4 +1,5 @@
 mod elems_cats;
+mod math;
 mod multi;
 #[cfg(test)]
 pub mod tests;
@@ -10,6 +11,25 @@ use anyhow::Error;
 // use crate::url::Url;
 use document_tree::{Document, HasChildren};
 
+/// How to render math
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MathOutput {
+    /// Convert LaTeX to `<math>` elements, which browsers display natively
+    #[default]
+    MathML,
+    /// Keep LaTeX in `\(…\)` and `\[…\]` delimiters, to be rendered by `MathJax` or `KaTeX`
+    MathJax,
+}
+
+/// Options for HTML rendering
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HTMLOptions {
+    /// Render a full HTML document instead of a fragment
+    pub standalone: bool,
+    /// How to render math
+    pub math_output: MathOutput,
+}
+
 /// Render document as HTML
 ///
 /// # Errors
@@ -18,8 +38,32 @@ pub fn render_html<W>(document: &Document, stream: W, standalone: bool) -> Resul
 where
     W: Write,
 {
-    let mut renderer = HTMLRenderer { stream, level: 0 };
-    if standalone {
+    let options = HTMLOptions {
+        standalone,
+        ..HTMLOptions::default()
+    };
+    render_html_with_options(document, stream, options)
+}
+
+/// Render document as HTML with the given options
+///
+/// # Errors
+/// Returns error if serialization fails
+pub fn render_html_with_options<W>(
+    document: &Document,
+    stream: W,
+    options: HTMLOptions,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    let mut renderer = HTMLRenderer {
+        stream,
+        level: 0,
+        math_output: options.math_output,
+        equations: 0,
+    };
+    if options.standalone {
         document.render_html(&mut renderer)
     } else {
         document.children().render_html(&mut renderer)
@@ -39,6 +83,9 @@ where
 {
     stream: W,
     level: u8,
+    math_output: MathOutput,
+    /// Number of labelled equations so far
+    equations: usize,
 }
 
 trait HTMLRender {
@@ -89,15 +136,21 @@ ol.footnotes > li > .backrefs {
     float: left;
     font-size: 0.8em;
 }
-</style>
-</head>"#;
+</style>"#;
+
+const MATHJAX: &str =
+    r#"<script defer src="https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-mml-chtml.js"></script>"#;
 
 impl HTMLRender for Document {
     fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        writeln!(renderer.stream, "<!doctype html>\n<html>\n{HEAD}\n<body>")?;
+        writeln!(renderer.stream, "<!doctype html>\n<html>\n{HEAD}")?;
+        if renderer.math_output == MathOutput::MathJax {
+            writeln!(renderer.stream, "{MATHJAX}")?;
+        }
+        writeln!(renderer.stream, "</head>\n<body>")?;
         self.children().render_html(renderer)?;
         writeln!(renderer.stream, "</body>\n</html>")?;
         Ok(())
@@ -110,9 +163,7 @@ impl HTMLRender for Document {
 
 //TODO: prettyprint option list
 //TODO: render admonitions: Admonition, Attention, Hint, Note, Caution, Danger, Error, Important, Tip, Warning
-//TODO: properly render tables
 
 //TODO: add reference target: FootnoteReference, CitationReference, TitleReference
 //TODO: add title: Abbr, Acronym
-//TODO: convert math, set display attr
 //TODO: add id: Rubric, Target, TargetInline
@@ -1,9 +1,11 @@
-use std::io::Write;
+use std::{io::Write, num::NonZero};
 
 use anyhow::{Error, bail};
 
 // use crate::url::Url;
-use super::{HTMLRender, HTMLRenderer, escape_html, footnote_symbol};
+use super::{
+    HTMLRender, HTMLRenderer, MathOutput, escape_html, footnote_symbol, math::latex_to_mathml,
+};
 use document_tree::{
     Element, ExtraAttributes, HasChildren, LabelledFootnote as _, attribute_types as at,
     element_categories as c, elements as e,
@@ -32,10 +34,14 @@ macro_rules! impl_html_render_simple {
         impl HTMLRender for e::$type {
             fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error> where W: Write {
                 write!(renderer.stream, "<{}", stringify!($tag))?;
+                if let Some(id) = self.ids().first() {
+                    write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+                }
                 if self.classes().len() > 0 {
                     write!(renderer.stream, " class=\"{}\"", self.classes().join(" "))?;
                 }
                 write!(renderer.stream, ">")?;
+                write_anchors(renderer, self.ids().get(1..).unwrap_or_default())?;
                 self.children().render_html(renderer)?;
                 write!(renderer.stream, "</{}>", stringify!($tag))?;
                 Ok(())
@@ -44,14 +50,21 @@ macro_rules! impl_html_render_simple {
     };
 }
 
-macro_rules! impl_html_render_simple_nochildren {( $($type:ident => $tag:ident),+ ) => { $(
-    impl HTMLRender for e::$type {
-        fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error> where W: Write {
-            write!(renderer.stream, "<{0}></{0}>", stringify!($tag))?;
-            Ok(())
-        }
+/// Writes empty elements for IDs an element can’t render itself,
+/// e.g. ones it got from internal hyperlink targets.
+fn write_anchors<W>(renderer: &mut HTMLRenderer<W>, ids: &[at::ID]) -> Result<(), Error>
+where
+    W: Write,
+{
+    for id in ids {
+        write!(
+            renderer.stream,
+            "<span id=\"{}\"></span>",
+            escape_html(&id.0)
+        )?;
     }
-)+ }}
+    Ok(())
+}
 
 // Impl
 
@@ -75,22 +88,104 @@ impl HTMLRender for e::Title {
             renderer.level
         };
         write!(renderer.stream, "<h{level}>")?;
+        // Link back to the table of contents
+        if let Some(refid) = &self.extra().refid {
+            write!(
+                renderer.stream,
+                "<a class=\"toc-backref\" href=\"#{}\">",
+                escape_html(&refid.0)
+            )?;
+        }
         self.children().render_html(renderer)?;
+        if self.extra().refid.is_some() {
+            write!(renderer.stream, "</a>")?;
+        }
         write!(renderer.stream, "</h{level}>")?;
         Ok(())
     }
 }
 
 impl HTMLRender for e::Docinfo {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
         // Like “YAML frontmatter” in Markdown
-        unimplemented!();
+        use c::BibliographicElement as B;
+
+        writeln!(renderer.stream, "<dl class=\"docinfo\">")?;
+        for elem in self.children() {
+            match elem {
+                B::Authors(e) => {
+                    write_docinfo_dt(renderer, "authors", "Authors")?;
+                    write!(renderer.stream, "<dd class=\"authors\">")?;
+                    for (i, author) in e.children().iter().enumerate() {
+                        if i > 0 {
+                            writeln!(renderer.stream)?;
+                        }
+                        write!(renderer.stream, "<p>")?;
+                        match author {
+                            c::AuthorInfo::Author(e) => e.children().render_html(renderer)?,
+                            c::AuthorInfo::Organization(e) => e.children().render_html(renderer)?,
+                            c::AuthorInfo::Address(e) => e.children().render_html(renderer)?,
+                            c::AuthorInfo::Contact(e) => e.children().render_html(renderer)?,
+                        }
+                        write!(renderer.stream, "</p>")?;
+                    }
+                    write!(renderer.stream, "</dd>")?;
+                }
+                B::Author(e) => write_docinfo_item(renderer, "author", "Author", e.children())?,
+                B::Organization(e) => {
+                    write_docinfo_item(renderer, "organization", "Organization", e.children())?;
+                }
+                B::Address(e) => write_docinfo_item(renderer, "address", "Address", e.children())?,
+                B::Contact(e) => write_docinfo_item(renderer, "contact", "Contact", e.children())?,
+                B::Version(e) => write_docinfo_item(renderer, "version", "Version", e.children())?,
+                B::Revision(e) => {
+                    write_docinfo_item(renderer, "revision", "Revision", e.children())?;
+                }
+                B::Status(e) => write_docinfo_item(renderer, "status", "Status", e.children())?,
+                B::Date(e) => write_docinfo_item(renderer, "date", "Date", e.children())?,
+                B::Copyright(e) => {
+                    write_docinfo_item(renderer, "copyright", "Copyright", e.children())?;
+                }
+                B::Field(e) => e.render_html(renderer)?,
+            }
+            writeln!(renderer.stream)?;
+        }
+        write!(renderer.stream, "</dl>")?;
+        Ok(())
     }
 }
 
+fn write_docinfo_dt<W>(
+    renderer: &mut HTMLRenderer<W>,
+    class: &str,
+    label: &str,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    writeln!(renderer.stream, "<dt class=\"{class}\">{label}</dt>")?;
+    Ok(())
+}
+
+fn write_docinfo_item<W>(
+    renderer: &mut HTMLRenderer<W>,
+    class: &str,
+    label: &str,
+    children: &[c::TextOrInlineElement],
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    write_docinfo_dt(renderer, class, label)?;
+    write!(renderer.stream, "<dd class=\"{class}\"><p>")?;
+    children.render_html(renderer)?;
+    write!(renderer.stream, "</p></dd>")?;
+    Ok(())
+}
+
 impl HTMLRender for e::Decoration {
     fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
@@ -108,7 +203,6 @@ impl_html_render_cat!(SubStructure {
     Section,
     BodyElement
 });
-impl_html_render_simple!(Sidebar => aside);
 
 impl HTMLRender for e::Section {
     fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
@@ -116,7 +210,12 @@ impl HTMLRender for e::Section {
         W: Write,
     {
         renderer.level += 1;
-        write!(renderer.stream, "<section id=\"{0}\">", self.ids()[0].0)?;
+        write!(renderer.stream, "<section id=\"{0}\"", self.ids()[0].0)?;
+        if !self.classes().is_empty() {
+            write!(renderer.stream, " class=\"{}\"", self.classes().join(" "))?;
+        }
+        write!(renderer.stream, ">")?;
+        write_anchors(renderer, &self.ids()[1..])?;
         self.children().render_html(renderer)?;
         write!(renderer.stream, "</section>")?;
         renderer.level -= 1;
@@ -129,18 +228,76 @@ impl HTMLRender for e::Transition {
     where
         W: Write,
     {
-        write!(renderer.stream, "<hr/>")?;
+        write!(renderer.stream, "<hr>")?;
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::Sidebar {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write!(renderer.stream, "<aside class=\"sidebar")?;
+        for class in self.classes() {
+            write!(renderer.stream, " {class}")?;
+        }
+        writeln!(renderer.stream, "\">")?;
+        for child in self.children() {
+            match child {
+                c::SubSidebar::Title(title) => {
+                    write!(renderer.stream, "<p class=\"sidebar-title\">")?;
+                    title.children().render_html(renderer)?;
+                    write!(renderer.stream, "</p>")?;
+                }
+                c::SubSidebar::Subtitle(subtitle) => {
+                    write!(renderer.stream, "<p class=\"sidebar-subtitle\">")?;
+                    subtitle.children().render_html(renderer)?;
+                    write!(renderer.stream, "</p>")?;
+                }
+                c::SubSidebar::Topic(topic) => topic.render_html(renderer)?,
+                c::SubSidebar::BodyElement(e) => e.render_html(renderer)?,
+            }
+            writeln!(renderer.stream)?;
+        }
+        write!(renderer.stream, "</aside>")?;
         Ok(())
     }
 }
 
 impl HTMLRender for e::Topic {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        // A mini section with title
-        unimplemented!();
+        // A mini section with title. Tables of contents are navigation.
+        let tag = if self.classes().iter().any(|c| c == "contents") {
+            "nav"
+        } else {
+            "aside"
+        };
+        write!(renderer.stream, "<{tag} class=\"topic")?;
+        for class in self.classes() {
+            write!(renderer.stream, " {class}")?;
+        }
+        write!(renderer.stream, "\"")?;
+        if let Some(id) = self.ids().first() {
+            write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+        }
+        writeln!(renderer.stream, ">")?;
+        for child in self.children() {
+            match child {
+                c::SubTopic::Title(title) => {
+                    write!(renderer.stream, "<p class=\"topic-title\">")?;
+                    title.children().render_html(renderer)?;
+                    write!(renderer.stream, "</p>")?;
+                }
+                c::SubTopic::BodyElement(e) => e.render_html(renderer)?,
+            }
+            writeln!(renderer.stream)?;
+        }
+        write!(renderer.stream, "</{tag}>")?;
+        Ok(())
     }
 }
 
@@ -181,8 +338,82 @@ impl_html_render_cat!(BodyElement {
     Figure,
     Table
 });
-impl_html_render_simple!(Paragraph => p, MathBlock => math, Rubric => a, Compound => p, Container => div, BulletList => ul, EnumeratedList => ol, DefinitionList => dl, FieldList => dl, OptionList => pre, LineBlock => div, BlockQuote => blockquote, Admonition => aside, Attention => aside, Hint => aside, Note => aside, Caution => aside, Danger => aside, Error => aside, Important => aside, Tip => aside, Warning => aside, Figure => figure);
-impl_html_render_simple_nochildren!(Table => table); //TODO: after implementing the table, move it to elems with children
+impl_html_render_simple!(Paragraph => p, Rubric => a, Compound => p, Container => div, BulletList => ul, DefinitionList => dl, BlockQuote => blockquote, Admonition => aside, Attention => aside, Hint => aside, Note => aside, Caution => aside, Danger => aside, Error => aside, Important => aside, Tip => aside, Warning => aside);
+
+impl HTMLRender for e::FieldList {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write_anchors(renderer, self.ids())?;
+        write!(renderer.stream, "<dl class=\"field-list")?;
+        for class in self.classes() {
+            write!(renderer.stream, " {class}")?;
+        }
+        write!(renderer.stream, "\">")?;
+        self.children().render_html(renderer)?;
+        write!(renderer.stream, "</dl>")?;
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::OptionList {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write_anchors(renderer, self.ids())?;
+        // Two columns like docutils’ HTML writer
+        write!(renderer.stream, "<table class=\"option-list")?;
+        for class in self.classes() {
+            write!(renderer.stream, " {class}")?;
+        }
+        writeln!(renderer.stream, "\">")?;
+        writeln!(renderer.stream, "<colgroup>")?;
+        writeln!(renderer.stream, "<col class=\"option\">")?;
+        writeln!(renderer.stream, "<col class=\"description\">")?;
+        writeln!(renderer.stream, "</colgroup>")?;
+        writeln!(renderer.stream, "<tbody>")?;
+        for item in self.children() {
+            item.render_html(renderer)?;
+            writeln!(renderer.stream)?;
+        }
+        writeln!(renderer.stream, "</tbody>")?;
+        write!(renderer.stream, "</table>")?;
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::EnumeratedList {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write_anchors(renderer, self.ids())?;
+        let extra = self.extra();
+        write!(renderer.stream, "<ol")?;
+        if let Some(enumtype) = extra.enumtype {
+            let typ = match enumtype {
+                at::EnumeratedListType::Arabic => "1",
+                at::EnumeratedListType::LowerAlpha => "a",
+                at::EnumeratedListType::UpperAlpha => "A",
+                at::EnumeratedListType::LowerRoman => "i",
+                at::EnumeratedListType::UpperRoman => "I",
+            };
+            write!(renderer.stream, " type=\"{typ}\"")?;
+        }
+        if let Some(start) = extra.start {
+            write!(renderer.stream, " start=\"{start}\"")?;
+        }
+        if !self.classes().is_empty() {
+            write!(renderer.stream, " class=\"{}\"", self.classes().join(" "))?;
+        }
+        write!(renderer.stream, ">")?;
+        self.children().render_html(renderer)?;
+        write!(renderer.stream, "</ol>")?;
+        Ok(())
+    }
+}
 
 // circumvent E0119
 trait IMark {}
@@ -205,6 +436,13 @@ where
             )?;
         }
         write!(renderer.stream, "<img")?;
+        if !self.classes().is_empty() {
+            write!(
+                renderer.stream,
+                " class=\"{}\"",
+                escape_html(&self.classes().join(" "))
+            )?;
+        }
         if let Some(alt) = extra.alt.as_ref() {
             write!(renderer.stream, " alt=\"{}\"", escape_html(alt))?;
         }
@@ -229,6 +467,7 @@ impl HTMLRender for e::LiteralBlock {
     where
         W: Write,
     {
+        write_anchors(renderer, self.ids())?;
         let mut cls_iter = self.classes().iter();
         let is_code = cls_iter.next() == Some(&"code".to_owned());
         write!(renderer.stream, "<pre>")?;
@@ -249,13 +488,53 @@ impl HTMLRender for e::LiteralBlock {
     }
 }
 
+impl HTMLRender for e::MathBlock {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let latex = self.children().concat();
+        write!(renderer.stream, "<div class=\"math")?;
+        for class in self.classes() {
+            write!(renderer.stream, " {class}")?;
+        }
+        write!(renderer.stream, "\"")?;
+        if let Some(id) = self.ids().first() {
+            write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+        }
+        write!(renderer.stream, ">")?;
+        match renderer.math_output {
+            MathOutput::MathML => write!(
+                renderer.stream,
+                "<math display=\"block\">{}</math>",
+                latex_to_mathml(&latex)
+            )?,
+            MathOutput::MathJax => write!(renderer.stream, "\\[{}\\]", escape_html(&latex))?,
+        }
+        // Labelled equations are numbered
+        if self.extra().label.is_some() {
+            renderer.equations += 1;
+            write!(
+                renderer.stream,
+                "<span class=\"eqno\">({})</span>",
+                renderer.equations
+            )?;
+        }
+        write!(renderer.stream, "</div>")?;
+        Ok(())
+    }
+}
+
 impl HTMLRender for e::DoctestBlock {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        // TODO
-        unimplemented!();
+        write_anchors(renderer, self.ids())?;
+        write!(renderer.stream, "<pre class=\"doctest-block\">")?;
+        self.children().render_html(renderer)?;
+        write!(renderer.stream, "</pre>")?;
+        Ok(())
     }
 }
 
@@ -292,11 +571,15 @@ impl HTMLRender for e::Pending {
 }
 
 impl HTMLRender for e::Target {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        // Should be resolved by now
+        // Internal targets without element to point to keep their IDs
+        let extra = self.extra();
+        if extra.refuri.is_none() && extra.refname.is_empty() {
+            write_anchors(renderer, self.ids())?;
+        }
         Ok(())
     }
 }
@@ -363,11 +646,46 @@ impl HTMLRender for e::Footnote {
 }
 
 impl HTMLRender for e::Citation {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        unimplemented!();
+        use c::SubFootnote::{BodyElement, Label};
+
+        write!(renderer.stream, "<div class=\"citation\"")?;
+        if let Some(id) = self.ids().first() {
+            write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+        }
+        write!(renderer.stream, ">")?;
+        for child in self.children() {
+            match child {
+                Label(label) => {
+                    write!(renderer.stream, "<span class=\"label\">[")?;
+                    label.children().render_html(renderer)?;
+                    write!(renderer.stream, "]</span>")?;
+                    // render backrefs after the label
+                    let backrefs = &self.extra().backrefs;
+                    if !backrefs.is_empty() {
+                        write!(renderer.stream, "<span class=\"backrefs\">(")?;
+                        for (i, refid) in backrefs.iter().enumerate() {
+                            if i > 0 {
+                                write!(renderer.stream, ", ")?;
+                            }
+                            write!(
+                                renderer.stream,
+                                "<a href=\"#{}\">{}</a>",
+                                escape_html(&refid.0),
+                                i + 1
+                            )?;
+                        }
+                        write!(renderer.stream, ")</span>")?;
+                    }
+                }
+                BodyElement(child) => child.render_html(renderer)?,
+            }
+        }
+        write!(renderer.stream, "</div>")?;
+        Ok(())
     }
 }
 
@@ -376,7 +694,11 @@ impl HTMLRender for e::SystemMessage {
     where
         W: Write,
     {
-        write!(renderer.stream, "<figure><caption>System Message</caption>")?;
+        write!(renderer.stream, "<figure")?;
+        if let Some(id) = self.ids().first() {
+            write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+        }
+        write!(renderer.stream, "><caption>System Message</caption>")?;
         self.children().render_html(renderer)?;
         write!(renderer.stream, "</figure>")?;
         Ok(())
@@ -405,7 +727,27 @@ impl_html_render_cat!(TextOrInlineElement {
     RawInline,
     ImageInline
 });
-impl_html_render_simple!(Emphasis => em, Strong => strong, Literal => code, CitationReference => a, TitleReference => a, Abbreviation => abbr, Acronym => acronym, Superscript => sup, Subscript => sub, Inline => span, Math => math, TargetInline => a);
+impl_html_render_simple!(Emphasis => em, Strong => strong, Literal => code, TitleReference => cite, Abbreviation => abbr, Acronym => acronym, Superscript => sup, Subscript => sub, Inline => span, TargetInline => a, Generated => span);
+
+impl HTMLRender for e::Math {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let latex = self.children().concat();
+        match renderer.math_output {
+            MathOutput::MathML => {
+                write!(renderer.stream, "<math>{}</math>", latex_to_mathml(&latex))?;
+            }
+            MathOutput::MathJax => write!(
+                renderer.stream,
+                "<span class=\"math\">\\({}\\)</span>",
+                escape_html(&latex)
+            )?,
+        }
+        Ok(())
+    }
+}
 
 impl HTMLRender for String {
     fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
@@ -424,12 +766,17 @@ impl HTMLRender for e::Reference {
     {
         let extra = self.extra();
         write!(renderer.stream, "<a")?;
+        if let Some(id) = self.ids().first() {
+            write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+        }
         if let Some(target) = extra.refuri.as_ref() {
             write!(
                 renderer.stream,
                 " href=\"{}\"",
                 escape_html(target.as_str())
             )?;
+        } else if let Some(refid) = extra.refid.as_ref() {
+            write!(renderer.stream, " href=\"#{}\"", escape_html(&refid.0))?;
         }
         /*
         if let Some(name) = extra.name.as_ref() {
@@ -454,12 +801,49 @@ impl HTMLRender for e::SubstitutionReference {
 }
 
 impl HTMLRender for e::Problematic {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
         // Broken inline markup leads to insertion of this in docutils
-        unimplemented!();
+        if let Some(refid) = &self.extra().refid {
+            // link to the system message explaining the problem
+            write!(
+                renderer.stream,
+                "<a class=\"problematic\" href=\"#{}\"",
+                escape_html(&refid.0)
+            )?;
+            if let Some(id) = self.ids().first() {
+                write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+            }
+            write!(renderer.stream, ">")?;
+            self.children().render_html(renderer)?;
+            write!(renderer.stream, "</a>")?;
+        } else {
+            write!(renderer.stream, "<span class=\"problematic\">")?;
+            self.children().render_html(renderer)?;
+            write!(renderer.stream, "</span>")?;
+        }
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::CitationReference {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write!(renderer.stream, "<a class=\"citation-reference\"")?;
+        if let Some(id) = self.ids().first() {
+            write!(renderer.stream, " id=\"{}\"", escape_html(&id.0))?;
+        }
+        if let Some(refid) = &self.extra().refid {
+            write!(renderer.stream, " href=\"#{}\"", escape_html(&refid.0))?;
+        }
+        write!(renderer.stream, ">[")?;
+        self.children().render_html(renderer)?;
+        write!(renderer.stream, "]</a>")?;
+        Ok(())
     }
 }
 
@@ -496,22 +880,18 @@ impl HTMLRender for e::FootnoteReference {
     }
 }
 
-impl HTMLRender for e::Generated {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
-    where
-        W: Write,
-    {
-        // Section numbers and so on
-        unimplemented!();
-    }
-}
-
 impl HTMLRender for e::RawInline {
     fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        self.children().render_html(renderer)
+        let extra = self.extra();
+        if extra.format.contains(&at::NameToken("html".to_owned())) {
+            for c in self.children() {
+                write!(renderer.stream, "{c}")?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -529,44 +909,147 @@ impl_html_render_cat!(SubSidebar {
 impl_html_render_simple!(ListItem => li);
 
 impl HTMLRender for e::DefinitionListItem {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        // Term→dt, Definition→dd, Classifier→???
-        unimplemented!();
+        // Term→dt, Classifier→span in dt, Definition→dd
+        write!(renderer.stream, "<dt>")?;
+        for child in self.children() {
+            match child {
+                c::SubDLItem::Term(term) => term.children().render_html(renderer)?,
+                c::SubDLItem::Classifier(classifier) => {
+                    write!(
+                        renderer.stream,
+                        "<span class=\"classifier-delimiter\">:</span> <span class=\"classifier\">"
+                    )?;
+                    classifier.children().render_html(renderer)?;
+                    write!(renderer.stream, "</span>")?;
+                }
+                c::SubDLItem::Definition(definition) => {
+                    write!(renderer.stream, "</dt>\n<dd>")?;
+                    definition.children().render_html(renderer)?;
+                    write!(renderer.stream, "</dd>")?;
+                }
+            }
+        }
+        Ok(())
     }
 }
 
 impl HTMLRender for e::Field {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
         // FieldName→dt, FieldBody→dd
-        unimplemented!();
+        let class = if self.classes().is_empty() {
+            String::new()
+        } else {
+            format!(" class=\"{}\"", self.classes().join(" "))
+        };
+        for child in self.children() {
+            match child {
+                c::SubField::FieldName(name) => {
+                    write!(renderer.stream, "<dt{class}>")?;
+                    name.children().render_html(renderer)?;
+                    writeln!(renderer.stream, "</dt>")?;
+                }
+                c::SubField::FieldBody(body) => {
+                    write!(renderer.stream, "<dd{class}>")?;
+                    body.children().render_html(renderer)?;
+                    write!(renderer.stream, "</dd>")?;
+                }
+            }
+        }
+        Ok(())
     }
 }
 
 impl HTMLRender for e::OptionListItem {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        // OptionGroup→dt(s), Description→dd
-        unimplemented!();
+        // OptionGroup→td, Description→td
+        writeln!(renderer.stream, "<tr>")?;
+        for child in self.children() {
+            match child {
+                c::SubOptionListItem::OptionGroup(group) => {
+                    write!(renderer.stream, "<td class=\"option-group\"><kbd>")?;
+                    for (i, option) in group.children().iter().enumerate() {
+                        if i > 0 {
+                            write!(renderer.stream, ", ")?;
+                        }
+                        option.render_html(renderer)?;
+                    }
+                    writeln!(renderer.stream, "</kbd></td>")?;
+                }
+                c::SubOptionListItem::Description(description) => {
+                    write!(renderer.stream, "<td>")?;
+                    description.children().render_html(renderer)?;
+                    writeln!(renderer.stream, "</td>")?;
+                }
+            }
+        }
+        write!(renderer.stream, "</tr>")?;
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::Option_ {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write!(renderer.stream, "<span class=\"option\">")?;
+        for child in self.children() {
+            match child {
+                c::SubOption::OptionString(string) => string.children().render_html(renderer)?,
+                c::SubOption::OptionArgument(arg) => {
+                    let delimiter = arg.extra().delimiter.as_deref().unwrap_or(" ");
+                    write!(renderer.stream, "{delimiter}<var>")?;
+                    arg.children().render_html(renderer)?;
+                    write!(renderer.stream, "</var>")?;
+                }
+            }
+        }
+        write!(renderer.stream, "</span>")?;
+        Ok(())
     }
 }
 
 impl_html_render_cat!(SubLineBlock { LineBlock, Line });
 
-impl HTMLRender for e::Line {
+impl HTMLRender for e::LineBlock {
     fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
+        write_anchors(renderer, self.ids())?;
+        write!(renderer.stream, "<div class=\"line-block")?;
+        for class in self.classes() {
+            write!(renderer.stream, " {class}")?;
+        }
+        write!(renderer.stream, "\">")?;
         self.children().render_html(renderer)?;
-        write!(renderer.stream, "<br>")?;
+        write!(renderer.stream, "</div>")?;
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::Line {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write!(renderer.stream, "<div class=\"line\">")?;
+        if self.children().is_empty() {
+            write!(renderer.stream, "<br>")?;
+        } else {
+            self.children().render_html(renderer)?;
+        }
+        write!(renderer.stream, "</div>")?;
         Ok(())
     }
 }
@@ -582,13 +1065,152 @@ impl_html_render_cat!(SubFigure {
     Legend,
     BodyElement
 });
-impl_html_render_simple!(Caption => caption);
+impl_html_render_simple!(Caption => figcaption);
+
+impl HTMLRender for e::Figure {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write_anchors(renderer, self.ids())?;
+        let extra = self.extra();
+        let mut classes = self.classes().clone();
+        if let Some(align) = extra.align {
+            classes.push(format!("align-{}", format!("{align:?}").to_lowercase()));
+        }
+        write!(renderer.stream, "<figure")?;
+        if !classes.is_empty() {
+            write!(renderer.stream, " class=\"{}\"", classes.join(" "))?;
+        }
+        if let Some(width) = &extra.width {
+            write!(renderer.stream, " style=\"width: {width}\"")?;
+        }
+        write!(renderer.stream, ">")?;
+        self.children().render_html(renderer)?;
+        write!(renderer.stream, "</figure>")?;
+        Ok(())
+    }
+}
 
 impl HTMLRender for e::Legend {
-    fn render_html<W>(&self, _renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
     where
         W: Write,
     {
-        unimplemented!();
+        write!(renderer.stream, "<div class=\"legend\">")?;
+        self.children().render_html(renderer)?;
+        write!(renderer.stream, "</div>")?;
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::Table {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        write_anchors(renderer, self.ids())?;
+        write!(renderer.stream, "<table")?;
+        if !self.classes().is_empty() {
+            write!(renderer.stream, " class=\"{}\"", self.classes().join(" "))?;
+        }
+        writeln!(renderer.stream, ">")?;
+        for c in self.children() {
+            match c {
+                c::SubTable::Title(title) => {
+                    write!(renderer.stream, "<caption>")?;
+                    title.children().render_html(renderer)?;
+                    writeln!(renderer.stream, "</caption>")?;
+                }
+                c::SubTable::TableGroup(group) => group.render_html(renderer)?,
+            }
+        }
+        write!(renderer.stream, "</table>")?;
+        Ok(())
+    }
+}
+
+impl HTMLRender for e::TableGroup {
+    fn render_html<W>(&self, renderer: &mut HTMLRenderer<W>) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let widths: Vec<usize> = self
+            .children()
+            .iter()
+            .filter_map(|c| match c {
+                c::SubTableGroup::TableColspec(spec) => spec.extra().colwidth.as_ref(),
+                _ => None,
+            })
+            .filter_map(|w| w.parse().ok())
+            .collect();
+        if let Some(total) = NonZero::new(widths.iter().sum::<usize>()) {
+            writeln!(renderer.stream, "<colgroup>")?;
+            for width in widths {
+                let percent = (width * 100 + total.get() / 2) / total;
+                writeln!(renderer.stream, "<col style=\"width: {percent}%\">")?;
+            }
+            writeln!(renderer.stream, "</colgroup>")?;
+        }
+        let stubs: Vec<bool> = self
+            .children()
+            .iter()
+            .filter_map(|c| match c {
+                c::SubTableGroup::TableColspec(spec) => Some(spec.extra().stub == Some(true)),
+                _ => None,
+            })
+            .collect();
+        for c in self.children() {
+            match c {
+                c::SubTableGroup::TableColspec(_) => {}
+                c::SubTableGroup::TableHead(head) => {
+                    writeln!(renderer.stream, "<thead>")?;
+                    render_table_rows(renderer, head.children(), "th", &[])?;
+                    writeln!(renderer.stream, "</thead>")?;
+                }
+                c::SubTableGroup::TableBody(body) => {
+                    writeln!(renderer.stream, "<tbody>")?;
+                    render_table_rows(renderer, body.children(), "td", &stubs)?;
+                    writeln!(renderer.stream, "</tbody>")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn render_table_rows<W>(
+    renderer: &mut HTMLRenderer<W>,
+    rows: &[e::TableRow],
+    tag: &str,
+    stubs: &[bool],
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    for row in rows {
+        writeln!(renderer.stream, "<tr>")?;
+        // Cells spanning rows from above are not counted, like in docutils
+        let mut col = 0;
+        for entry in row.children() {
+            let is_stub = stubs.get(col).copied().unwrap_or(false);
+            col += entry.extra().morecols.unwrap_or(0) + 1;
+            let tag = if is_stub { "th" } else { tag };
+            write!(renderer.stream, "<{tag}")?;
+            if is_stub {
+                write!(renderer.stream, " class=\"stub\"")?;
+            }
+            if let Some(morerows) = entry.extra().morerows {
+                write!(renderer.stream, " rowspan=\"{}\"", morerows + 1)?;
+            }
+            if let Some(morecols) = entry.extra().morecols {
+                write!(renderer.stream, " colspan=\"{}\"", morecols + 1)?;
+            }
+            write!(renderer.stream, ">")?;
+            entry.children().render_html(renderer)?;
+            writeln!(renderer.stream, "</{tag}>")?;
+        }
+        writeln!(renderer.stream, "</tr>")?;
     }
+    Ok(())
 }
@@ -0,0 +1,540 @@
+/*! Convert LaTeX math to `MathML`.
+ *
+ * Supports the commonly used subset of LaTeX math:
+ * scripts, fractions, roots, fences, fonts, text, spacing, greek letters, operators,
+ * and the `matrix`, `pmatrix`, `bmatrix`, `vmatrix`, `cases`, and `aligned` environments.
+ * Unknown commands are rendered as `<merror>`.
+ */
+
+use std::{iter::Peekable, str::Chars};
+
+use super::escape_html;
+
+/// Converts LaTeX math to the content of a `<math>` element.
+pub(super) fn latex_to_mathml(latex: &str) -> String {
+    let mut parser = Parser {
+        chars: latex.chars().peekable(),
+    };
+    mrow(parser.parse_rows(None))
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Command(String),
+    Char(char),
+    Open,
+    Close,
+    Sup,
+    Sub,
+    /// `&`
+    Align,
+    /// `\\`
+    NewRow,
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+/// A table of cells, each a list of `MathML` nodes. Expressions without `&` or `\\` have one cell.
+type Rows = Vec<Vec<Vec<String>>>;
+
+impl Parser<'_> {
+    fn next_token(&mut self) -> Option<Token> {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let ch = self.chars.next()?;
+        Some(match ch {
+            '\\' => match self.chars.next() {
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let mut name = c.to_string();
+                    while let Some(c) = self.chars.next_if(char::is_ascii_alphabetic) {
+                        name.push(c);
+                    }
+                    Token::Command(name)
+                }
+                Some('\\') => Token::NewRow,
+                Some(c) => Token::Command(c.to_string()),
+                None => Token::Char('\\'),
+            },
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '^' => Token::Sup,
+            '_' => Token::Sub,
+            '&' => Token::Align,
+            c => Token::Char(c),
+        })
+    }
+
+    fn peek_token(&mut self) -> Option<Token> {
+        let saved = self.chars.clone();
+        let token = self.next_token();
+        self.chars = saved;
+        token
+    }
+
+    /// Parses until the end, a closing brace, `\right`, or `\end`,
+    /// splitting cells at `&` and rows at `\\`.
+    fn parse_rows(&mut self, end: Option<&str>) -> Rows {
+        let mut rows: Rows = vec![vec![vec![]]];
+        loop {
+            let cell = rows
+                .last_mut()
+                .and_then(|r| r.last_mut())
+                .expect("nonempty");
+            match self.peek_token() {
+                None => break,
+                Some(Token::Close) => {
+                    self.next_token();
+                    break;
+                }
+                Some(Token::Command(cmd)) if cmd == "right" || cmd == "end" => {
+                    if end.is_some() {
+                        break;
+                    }
+                    self.next_token();
+                }
+                Some(Token::Align) => {
+                    self.next_token();
+                    rows.last_mut().expect("nonempty").push(vec![]);
+                }
+                Some(Token::NewRow) => {
+                    self.next_token();
+                    rows.push(vec![vec![]]);
+                }
+                Some(_) => {
+                    let Some(node) = self.parse_scripted() else {
+                        break;
+                    };
+                    cell.push(node);
+                }
+            }
+        }
+        rows
+    }
+
+    /// Parses a group up to its closing brace into a single node.
+    fn parse_group(&mut self) -> String {
+        mrow(self.parse_rows(None))
+    }
+
+    /// Parses an atom with optional sub- and superscripts.
+    fn parse_scripted(&mut self) -> Option<String> {
+        let base = self.parse_atom()?;
+        let (mut sub, mut sup) = (None, None);
+        loop {
+            match self.peek_token() {
+                Some(Token::Sub) if sub.is_none() => {
+                    self.next_token();
+                    sub = Some(self.parse_atom().unwrap_or_default());
+                }
+                Some(Token::Sup) if sup.is_none() => {
+                    self.next_token();
+                    sup = Some(self.parse_atom().unwrap_or_default());
+                }
+                Some(Token::Char('\'')) if sup.is_none() => {
+                    self.next_token();
+                    sup = Some("<mo>′</mo>".to_owned());
+                }
+                _ => break,
+            }
+        }
+        let under = base.starts_with("<mo largeop") || base.starts_with("<mi>lim");
+        Some(match (sub, sup) {
+            (None, None) => base,
+            (Some(sub), None) if under => format!("<munder>{base}{sub}</munder>"),
+            (Some(sub), None) => format!("<msub>{base}{sub}</msub>"),
+            (None, Some(sup)) => format!("<msup>{base}{sup}</msup>"),
+            (Some(sub), Some(sup)) if under => {
+                format!("<munderover>{base}{sub}{sup}</munderover>")
+            }
+            (Some(sub), Some(sup)) => format!("<msubsup>{base}{sub}{sup}</msubsup>"),
+        })
+    }
+
+    /// Parses a single node. Returns `None` at the end of a group.
+    fn parse_atom(&mut self) -> Option<String> {
+        Some(match self.next_token()? {
+            Token::Open => self.parse_group(),
+            Token::Close => return None,
+            Token::Char(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                format!("<mn>{number}</mn>")
+            }
+            Token::Char(c) if c.is_alphabetic() => format!("<mi>{c}</mi>"),
+            Token::Char(c) => format!("<mo>{}</mo>", escape_html(&c.to_string())),
+            Token::Sup | Token::Sub | Token::Align | Token::NewRow => "<mrow></mrow>".to_owned(),
+            Token::Command(cmd) => self.parse_command(&cmd),
+        })
+    }
+
+    /// Reads a brace-delimited argument as raw text.
+    fn raw_argument(&mut self) -> String {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if self.chars.next_if_eq(&'{').is_none() {
+            return self.chars.next().map(String::from).unwrap_or_default();
+        }
+        let mut depth = 0;
+        let mut text = String::new();
+        for c in self.chars.by_ref() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            text.push(c);
+        }
+        text
+    }
+
+    fn parse_command(&mut self, cmd: &str) -> String {
+        if let Some(symbol) = symbol(cmd) {
+            return symbol;
+        }
+        match cmd {
+            "frac" | "dfrac" | "tfrac" => {
+                let num = self.parse_atom().unwrap_or_default();
+                let den = self.parse_atom().unwrap_or_default();
+                format!("<mfrac>{num}{den}</mfrac>")
+            }
+            "binom" => {
+                let n = self.parse_atom().unwrap_or_default();
+                let k = self.parse_atom().unwrap_or_default();
+                format!(
+                    "<mrow><mo>(</mo><mfrac linethickness=\"0\">{n}{k}</mfrac><mo>)</mo></mrow>"
+                )
+            }
+            "sqrt" => {
+                while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if self.chars.next_if_eq(&'[').is_some() {
+                    let index: String = self.chars.by_ref().take_while(|&c| c != ']').collect();
+                    let index = latex_to_mrow(&index);
+                    let radicand = self.parse_atom().unwrap_or_default();
+                    format!("<mroot>{radicand}{index}</mroot>")
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.parse_atom().unwrap_or_default())
+                }
+            }
+            "text" | "textrm" | "mbox" | "operatorname" => {
+                let text = escape_html(&self.raw_argument());
+                if cmd == "operatorname" {
+                    format!("<mi>{text}</mi>")
+                } else {
+                    format!("<mtext>{text}</mtext>")
+                }
+            }
+            "mathrm" | "mathbf" | "mathit" | "mathbb" | "mathcal" | "mathfrak" | "mathsf"
+            | "mathtt" | "boldsymbol" => {
+                let variant = match cmd {
+                    "mathrm" => "normal",
+                    "mathbf" | "boldsymbol" => "bold",
+                    "mathit" => "italic",
+                    "mathbb" => "double-struck",
+                    "mathcal" => "script",
+                    "mathfrak" => "fraktur",
+                    "mathsf" => "sans-serif",
+                    _ => "monospace",
+                };
+                let content = self.parse_atom().unwrap_or_default();
+                format!("<mstyle mathvariant=\"{variant}\">{content}</mstyle>")
+            }
+            "hat" | "bar" | "vec" | "dot" | "ddot" | "tilde" | "overline" | "widehat"
+            | "widetilde" => {
+                let accent = match cmd {
+                    "hat" | "widehat" => "^",
+                    "bar" | "overline" => "¯",
+                    "vec" => "→",
+                    "dot" => "˙",
+                    "ddot" => "¨",
+                    _ => "~",
+                };
+                let base = self.parse_atom().unwrap_or_default();
+                format!("<mover accent=\"true\">{base}<mo>{accent}</mo></mover>")
+            }
+            "left" => {
+                let open = self.fence();
+                let rows = self.parse_rows(Some("right"));
+                let close = match self.next_token() {
+                    Some(Token::Command(c)) if c == "right" => self.fence(),
+                    _ => String::new(),
+                };
+                format!(
+                    "<mrow>{}{}{}</mrow>",
+                    fence_mo(&open),
+                    mrow(rows),
+                    fence_mo(&close)
+                )
+            }
+            "begin" => {
+                let env = self.raw_argument();
+                let rows = self.parse_rows(Some("end"));
+                if matches!(self.next_token(), Some(Token::Command(c)) if c == "end") {
+                    self.raw_argument();
+                }
+                let table = mtable(rows);
+                let (open, close) = match env.as_str() {
+                    "pmatrix" => ("(", ")"),
+                    "bmatrix" => ("[", "]"),
+                    "Bmatrix" => ("{", "}"),
+                    "vmatrix" => ("|", "|"),
+                    "Vmatrix" => ("‖", "‖"),
+                    "cases" => ("{", ""),
+                    _ => ("", ""),
+                };
+                format!("<mrow>{}{table}{}</mrow>", fence_mo(open), fence_mo(close))
+            }
+            cmd => format!("<merror><mtext>\\{}</mtext></merror>", escape_html(cmd)),
+        }
+    }
+
+    /// Reads the delimiter after `\left` or `\right`.
+    fn fence(&mut self) -> String {
+        match self.next_token() {
+            Some(Token::Char('.')) | None => String::new(),
+            Some(Token::Char(c)) => c.to_string(),
+            Some(Token::Command(c)) => match c.as_str() {
+                "{" | "lbrace" => "{".to_owned(),
+                "}" | "rbrace" => "}".to_owned(),
+                "|" | "Vert" => "‖".to_owned(),
+                "langle" => "⟨".to_owned(),
+                "rangle" => "⟩".to_owned(),
+                "lfloor" => "⌊".to_owned(),
+                "rfloor" => "⌋".to_owned(),
+                "lceil" => "⌈".to_owned(),
+                "rceil" => "⌉".to_owned(),
+                _ => String::new(),
+            },
+            Some(_) => String::new(),
+        }
+    }
+}
+
+/// Converts commands without arguments.
+fn symbol(cmd: &str) -> Option<String> {
+    if let Some(symbol) = greek(cmd).or_else(|| identifier(cmd)) {
+        return Some(format!("<mi>{symbol}</mi>"));
+    }
+    if let Some(symbol) = operator(cmd) {
+        return Some(format!("<mo>{symbol}</mo>"));
+    }
+    if let Some(symbol) = large_operator(cmd) {
+        return Some(format!("<mo largeop=\"true\">{symbol}</mo>"));
+    }
+    if let Some(width) = space(cmd) {
+        return Some(format!("<mspace width=\"{width}\"></mspace>"));
+    }
+    Some(match cmd {
+        "sin" | "cos" | "tan" | "cot" | "sec" | "csc" | "arcsin" | "arccos" | "arctan" | "sinh"
+        | "cosh" | "tanh" | "log" | "ln" | "lg" | "exp" | "lim" | "max" | "min" | "sup" | "inf"
+        | "det" | "dim" | "gcd" | "deg" | "arg" | "ker" | "Pr" => format!("<mi>{cmd}</mi>"),
+        "{" | "}" | "%" | "$" | "#" | "_" => format!("<mo>{cmd}</mo>"),
+        "|" => "<mo>‖</mo>".to_owned(),
+        _ => return None,
+    })
+}
+
+fn latex_to_mrow(latex: &str) -> String {
+    let mut parser = Parser {
+        chars: latex.chars().peekable(),
+    };
+    parser.parse_group()
+}
+
+fn fence_mo(fence: &str) -> String {
+    if fence.is_empty() {
+        return String::new();
+    }
+    format!("<mo stretchy=\"true\">{}</mo>", escape_html(fence))
+}
+
+/// Wraps the nodes in a row, or a table if there are several cells.
+fn mrow(rows: Rows) -> String {
+    if rows.len() == 1 && rows[0].len() == 1 {
+        let nodes = &rows[0][0];
+        if nodes.len() == 1 {
+            return nodes[0].clone();
+        }
+        return format!("<mrow>{}</mrow>", nodes.concat());
+    }
+    mtable(rows)
+}
+
+fn mtable(rows: Rows) -> String {
+    let mut table = "<mtable>".to_owned();
+    for row in rows {
+        if row.iter().all(Vec::is_empty) {
+            continue;
+        }
+        table.push_str("<mtr>");
+        for cell in row {
+            table.push_str("<mtd>");
+            table.push_str(&cell.concat());
+            table.push_str("</mtd>");
+        }
+        table.push_str("</mtr>");
+    }
+    table.push_str("</mtable>");
+    table
+}
+
+fn greek(cmd: &str) -> Option<char> {
+    Some(match cmd {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "varepsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "varphi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    })
+}
+
+fn identifier(cmd: &str) -> Option<char> {
+    Some(match cmd {
+        "infty" => '∞',
+        "partial" => '∂',
+        "nabla" => '∇',
+        "hbar" => 'ℏ',
+        "ell" => 'ℓ',
+        "emptyset" | "varnothing" => '∅',
+        "aleph" => 'ℵ',
+        "Re" => 'ℜ',
+        "Im" => 'ℑ',
+        _ => return None,
+    })
+}
+
+fn operator(cmd: &str) -> Option<char> {
+    Some(match cmd {
+        "times" => '×',
+        "cdot" => '⋅',
+        "div" => '÷',
+        "pm" => '±',
+        "mp" => '∓',
+        "ast" => '∗',
+        "star" => '⋆',
+        "circ" => '∘',
+        "bullet" => '∙',
+        "leq" | "le" => '≤',
+        "geq" | "ge" => '≥',
+        "neq" | "ne" => '≠',
+        "ll" => '≪',
+        "gg" => '≫',
+        "approx" => '≈',
+        "sim" => '∼',
+        "simeq" => '≃',
+        "cong" => '≅',
+        "equiv" => '≡',
+        "propto" => '∝',
+        "in" => '∈',
+        "notin" => '∉',
+        "ni" => '∋',
+        "subset" => '⊂',
+        "supset" => '⊃',
+        "subseteq" => '⊆',
+        "supseteq" => '⊇',
+        "cup" => '∪',
+        "cap" => '∩',
+        "setminus" => '∖',
+        "wedge" | "land" => '∧',
+        "vee" | "lor" => '∨',
+        "neg" | "lnot" => '¬',
+        "forall" => '∀',
+        "exists" => '∃',
+        "to" | "rightarrow" => '→',
+        "leftarrow" | "gets" => '←',
+        "leftrightarrow" => '↔',
+        "Rightarrow" | "implies" => '⇒',
+        "Leftarrow" => '⇐',
+        "Leftrightarrow" | "iff" => '⇔',
+        "mapsto" => '↦',
+        "uparrow" => '↑',
+        "downarrow" => '↓',
+        "ldots" | "dots" => '…',
+        "cdots" => '⋯',
+        "vdots" => '⋮',
+        "ddots" => '⋱',
+        "langle" => '⟨',
+        "rangle" => '⟩',
+        "lfloor" => '⌊',
+        "rfloor" => '⌋',
+        "lceil" => '⌈',
+        "rceil" => '⌉',
+        "perp" => '⊥',
+        "parallel" => '∥',
+        "mid" => '∣',
+        "oplus" => '⊕',
+        "otimes" => '⊗',
+        "prime" => '′',
+        "angle" => '∠',
+        "degree" => '°',
+        _ => return None,
+    })
+}
+
+fn large_operator(cmd: &str) -> Option<char> {
+    Some(match cmd {
+        "sum" => '∑',
+        "prod" => '∏',
+        "coprod" => '∐',
+        "int" => '∫',
+        "iint" => '∬',
+        "iiint" => '∭',
+        "oint" => '∮',
+        "bigcup" => '⋃',
+        "bigcap" => '⋂',
+        "bigoplus" => '⨁',
+        "bigotimes" => '⨂',
+        _ => return None,
+    })
+}
+
+fn space(cmd: &str) -> Option<&'static str> {
+    Some(match cmd {
+        "," => "0.167em",
+        ":" | ">" => "0.222em",
+        ";" => "0.278em",
+        " " => "0.25em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        "!" => "-0.167em",
+        _ => return None,
+    })
+}
@@ -112,14 +112,13 @@ where
 impl_html_render_multi!(
     c::TextOrInlineElement,
     c::SubSidebar,
-    c::SubLineBlock,
+    c::SubLineBlock["\n"],
     c::SubBlockQuote,
     c::SubTopic,
-    c::SubFigure,
+    c::SubFigure["\n"],
     e::ListItem["\n"],
-    e::DefinitionListItem,
-    e::Field,
-    e::OptionListItem,
+    e::DefinitionListItem["\n"],
+    e::Field["\n"],
     e::Footnote["\n"],
     String
 );
@@ -2,7 +2,7 @@ use pretty_assertions::assert_eq;
 
 use rst_parser::parse;
 
-use crate::html::render_html;
+use crate::html::{HTMLOptions, MathOutput, render_html, render_html_with_options};
 
 fn check_renders_to(rst: &str, expected: &str) {
     println!("Rendering:\n{rst}\n---");
@@ -35,18 +35,19 @@ fn inline_literal() {
     );
 }
 
-/*
 #[test]
 fn test_reference_anonymous() {
-    check_renders_to("\
+    check_renders_to(
+        "\
 A simple `anonymous reference`__
 
 __ http://www.test.com/test_url
-", "\
+",
+        "\
 <p>A simple <a href=\"http://www.test.com/test_url\">anonymous reference</a></p>\
-");
+",
+    );
 }
-*/
 
 #[test]
 fn two_paragraphs() {
@@ -154,6 +155,80 @@ Footnotes 1: [#]_, 3: [#]_, 33: [33]_, \\*: [*]_, 2: [#named]_, 4: [#]_.
     );
 }
 
+#[test]
+fn citation() {
+    check_renders_to(
+        "\
+See [Smith-2020]_ and [Nobody]_.
+
+.. [Smith-2020] A book.
+",
+        "\
+<p>See <a class=\"citation-reference\" id=\"citation-reference-1\" href=\"#smith-2020\">[Smith-2020]</a> \
+and <a class=\"problematic\" href=\"#system-message-1\" id=\"problematic-1\">[Nobody]_</a>.</p>
+<div class=\"citation\" id=\"smith-2020\"><span class=\"label\">[Smith-2020]</span>\
+<span class=\"backrefs\">(<a href=\"#citation-reference-1\">1</a>)</span><p>A book.</p></div>
+<section id=\"docutils-system-messages\" class=\"system-messages\">
+<h1>Docutils System Messages</h1>
+<figure id=\"system-message-1\"><caption>System Message</caption><p>Unknown target name: “nobody”.</p></figure>
+</section>\
+",
+    );
+}
+
+#[test]
+fn internal_target() {
+    check_renders_to(
+        "\
+Go to `the list`_ or the section__.
+
+.. _the list:
+
+- Item
+
+.. __:
+.. _second:
+
+Section
+=======
+",
+        "\
+<p>Go to <a href=\"#the-list\">the list</a> or the <a href=\"#target-1\">section</a>.</p>
+
+<ul id=\"the-list\"><li><p>Item</p></li></ul>
+
+
+<section id=\"section\"><span id=\"target-1\"></span><span id=\"second\"></span><h1>Section</h1></section>\
+",
+    );
+}
+
+#[test]
+fn section_target() {
+    check_renders_to(
+        "\
+Café Crème
+==========
+
+See `café crème`_.
+
+Notes
+-----
+
+Notes
+-----
+",
+        "\
+<section id=\"cafe-creme\">
+<h1>Café Crème</h1>
+<p>See <a href=\"#cafe-creme\">café crème</a>.</p>
+<section id=\"notes\"><h2>Notes</h2></section>
+<section id=\"notes-1\"><h2>Notes</h2></section>
+</section>\
+",
+    );
+}
+
 /*
 #[test]
 fn test_section_hierarchy() {
@@ -323,6 +398,375 @@ fn bullet_list() {
     );
 }
 
+#[test]
+fn enumerated_list() {
+    check_renders_to(
+        "\
+B. bee
+C. sea
+
+   #. nested
+",
+        "\
+<ol type=\"A\" start=\"2\">
+<li><p>bee</p></li>
+<li>
+<p>sea</p>
+<ol type=\"1\"><li><p>nested</p></li></ol>
+</li>
+</ol>\
+",
+    );
+}
+
+#[test]
+fn definition_list() {
+    check_renders_to(
+        "\
+term
+   Definition.
+
+*term* 2 : classifier
+   Paragraph 1.
+
+   Paragraph 2.
+",
+        "\
+<dl>
+<dt>term</dt>
+<dd><p>Definition.</p></dd>
+<dt><em>term</em> 2<span class=\"classifier-delimiter\">:</span> <span class=\"classifier\">classifier</span></dt>
+<dd>
+<p>Paragraph 1.</p>
+<p>Paragraph 2.</p>
+</dd>
+</dl>\
+",
+    );
+}
+
+#[test]
+fn field_list() {
+    check_renders_to(
+        "\
+Not a docinfo.
+
+:This is: a
+:simple field list with loooong field: names
+",
+        "\
+<p>Not a docinfo.</p>
+<dl class=\"field-list\">
+<dt>This is</dt>
+<dd><p>a</p></dd>
+<dt>simple field list with loooong field</dt>
+<dd><p>names</p></dd>
+</dl>\
+",
+    );
+}
+
+#[test]
+fn docinfo() {
+    check_renders_to(
+        "\
+:Version: 1.2
+:Date: 2024-05-01
+
+Some stuff
+",
+        "\
+<dl class=\"docinfo\">
+<dt class=\"version\">Version</dt>
+<dd class=\"version\"><p>1.2</p></dd>
+<dt class=\"date\">Date</dt>
+<dd class=\"date\"><p>2024-05-01</p></dd>
+</dl>
+<p>Some stuff</p>\
+",
+    );
+}
+
+#[test]
+fn option_list() {
+    check_renders_to(
+        "\
+-a, --all     Show all entries,
+              including hidden ones.
+-f FILE, --file=<FILE>
+              Read from a file.
+",
+        "\
+<table class=\"option-list\">
+<colgroup>
+<col class=\"option\">
+<col class=\"description\">
+</colgroup>
+<tbody>
+<tr>
+<td class=\"option-group\"><kbd><span class=\"option\">-a</span>, <span class=\"option\">--all</span></kbd></td>
+<td><p>Show all entries, including hidden ones.</p></td>
+</tr>
+<tr>
+<td class=\"option-group\"><kbd><span class=\"option\">-f <var>FILE</var></span>, <span class=\"option\">--file=<var>&lt;FILE&gt;</var></span></kbd></td>
+<td><p>Read from a file.</p></td>
+</tr>
+</tbody>
+</table>\
+",
+    );
+}
+
+#[test]
+fn line_block() {
+    check_renders_to(
+        "\
+| Lend us a couple of bones.
+|    Nested *line*,
+     continued.
+|
+| Back.
+",
+        "\
+<div class=\"line-block\">
+<div class=\"line\">Lend us a couple of bones.</div>
+<div class=\"line-block\">
+<div class=\"line\">Nested <em>line</em>, continued.</div>
+<div class=\"line\"><br></div>
+</div>
+<div class=\"line\">Back.</div>
+</div>\
+",
+    );
+}
+
+#[test]
+fn doctest_block() {
+    check_renders_to(
+        "\
+>>> print('<this> is a doctest block')
+<this> is a doctest block
+",
+        "\
+<pre class=\"doctest-block\">&gt;&gt;&gt; print('&lt;this&gt; is a doctest block')
+&lt;this&gt; is a doctest block
+</pre>\
+",
+    );
+}
+
+#[test]
+fn transition() {
+    check_renders_to(
+        "\
+Before
+
+----------
+
+After
+",
+        "\
+<p>Before</p>
+<hr>
+<p>After</p>\
+",
+    );
+}
+
+#[test]
+fn figure() {
+    check_renders_to(
+        "\
+.. figure:: pic.png
+   :alt: A picture
+   :figwidth: 50%
+   :align: center
+
+   The *caption*.
+
+   A legend.
+",
+        "\
+<figure class=\"align-center\" style=\"width: 50%\">
+<img alt=\"A picture\" src=\"pic.png\" />
+<figcaption>The <em>caption</em>.</figcaption>
+<div class=\"legend\"><p>A legend.</p></div>
+</figure>\
+",
+    );
+}
+
+#[test]
+fn figure_classes() {
+    check_renders_to(
+        "\
+.. figure:: pic.png
+   :class: border
+   :figclass: wide
+
+   Caption
+",
+        "\
+<figure class=\"wide\">
+<img class=\"border\" src=\"pic.png\" />
+<figcaption>Caption</figcaption>
+</figure>\
+",
+    );
+}
+
+#[test]
+fn sidebar() {
+    check_renders_to(
+        "\
+.. sidebar:: Title
+   :subtitle: Subtitle
+
+   .. topic:: Topic
+
+      Topic content.
+",
+        "\
+<aside class=\"sidebar\">
+<p class=\"sidebar-title\">Title</p>
+<p class=\"sidebar-subtitle\">Subtitle</p>
+<aside class=\"topic\">
+<p class=\"topic-title\">Topic</p>
+<p>Topic content.</p>
+</aside>
+</aside>\
+",
+    );
+}
+
+#[test]
+fn contents() {
+    check_renders_to(
+        "\
+.. contents::
+
+Section
+=======
+",
+        "\
+<nav class=\"topic contents\" id=\"contents\">
+<p class=\"topic-title\">Contents</p>
+<ul><li><p><a id=\"toc-entry-1\" href=\"#section\">Section</a></p></li></ul>
+</nav>
+<section id=\"section\"><h1><a class=\"toc-backref\" href=\"#toc-entry-1\">Section</a></h1></section>\
+",
+    );
+}
+
+#[test]
+fn contents_substitution() {
+    check_renders_to(
+        "\
+.. contents::
+
+Title |s|
+=========
+
+Link |l|
+========
+
+.. |s| replace:: x
+.. |l| replace:: `y <https://example.com/>`_
+",
+        "\
+<nav class=\"topic contents\" id=\"contents\">
+<p class=\"topic-title\">Contents</p>
+<ul>
+<li><p><a id=\"toc-entry-1\" href=\"#title\">Title x</a></p></li>
+<li><p><a id=\"toc-entry-2\" href=\"#link\">Link y</a></p></li>
+</ul>
+</nav>
+<section id=\"title\"><h1><a class=\"toc-backref\" href=\"#toc-entry-1\">Title x</a></h1></section>
+<section id=\"link\"><h1>Link <a href=\"https://example.com/\">y</a></h1></section>\
+",
+    );
+}
+
+#[test]
+fn sectnum() {
+    check_renders_to(
+        "\
+.. sectnum::
+   :start: 3
+
+Section
+=======
+",
+        "<section id=\"section\"><h1><span class=\"sectnum\">3\u{a0}\u{a0}\u{a0}</span>Section</h1></section>",
+    );
+}
+
+#[test]
+fn list_table() {
+    check_renders_to(
+        "\
+.. list-table::
+   :stub-columns: 1
+   :widths: auto
+
+   * - a
+     - b
+",
+        "\
+<table class=\"colwidths-auto\">
+<tbody>
+<tr>
+<th class=\"stub\"><p>a</p></th>
+<td><p>b</p></td>
+</tr>
+</tbody>
+</table>\
+",
+    );
+}
+
+#[test]
+fn math() {
+    check_renders_to(
+        "\
+:math:`x_1^2`
+
+.. math::
+   :label: frac
+
+   \\frac{\\alpha}{2} \\leq \\sqrt{y}
+",
+        "\
+<p><math><msubsup><mi>x</mi><mn>1</mn><mn>2</mn></msubsup></math></p>
+<div class=\"math\" id=\"frac\"><math display=\"block\"><mrow><mfrac><mi>α</mi><mn>2</mn></mfrac><mo>≤</mo><msqrt><mi>y</mi></msqrt></mrow></math><span class=\"eqno\">(1)</span></div>\
+",
+    );
+}
+
+#[test]
+fn math_mathjax() {
+    let doc = parse(".. math:: a < b\n\nInline :math:`x`\n").expect("Cannot parse");
+    let mut result_data: Vec<u8> = vec![];
+    let options = HTMLOptions {
+        standalone: false,
+        math_output: MathOutput::MathJax,
+    };
+    render_html_with_options(&doc, &mut result_data, options).expect("Render error");
+    let result = String::from_utf8(result_data).expect("Could not decode");
+    assert_eq!(
+        result.trim(),
+        "<div class=\"math\">\\[a &lt; b\\]</div>\n<p>Inline <span class=\"math\">\\(x\\)</span></p>",
+    );
+}
+
+#[test]
+fn roles() {
+    check_renders_to(
+        ":emphasis:`a` `b` H\\ :sub:`2`\\ O :rfc:`2822` :raw-html:`<br>`",
+        "<p><em>a</em> <cite>b</cite> H<sub>2</sub>O <a href=\"https://tools.ietf.org/html/rfc2822.html\">RFC 2822</a> <br></p>",
+    );
+}
+
 /*
 #[test]
 fn test_table() {
@@ -458,24 +902,83 @@ Not a docinfo.
 }
 */
 
-/*
 #[test]
-fn test_field_list_long() {
-    check_renders_to("\
-Not a docinfo.
+fn grid_table() {
+    check_renders_to(
+        "\
++-----+-----+
+| 1   | 2   |
++=====+=====+
+| 3         |
++-----+-----+
+| 4   | 5   |
+|     +-----+
+|     | 6   |
++-----+-----+
+",
+        "\
+<table>
+<colgroup>
+<col style=\"width: 50%\">
+<col style=\"width: 50%\">
+</colgroup>
+<thead>
+<tr>
+<th><p>1</p></th>
+<th><p>2</p></th>
+</tr>
+</thead>
+<tbody>
+<tr>
+<td colspan=\"2\"><p>3</p></td>
+</tr>
+<tr>
+<td rowspan=\"2\"><p>4</p></td>
+<td><p>5</p></td>
+</tr>
+<tr>
+<td><p>6</p></td>
+</tr>
+</tbody>
+</table>\
+",
+    );
+}
 
-:This is: a
-:simple field list with loooong field: names
-", "\
-<p>Not a docinfo.</p>
-<dl class=\"field-list\">
-<dt>This is</dt>
-<dd><p>a</p>
-</dd>
-<dt>simple field list with loooong field</dt>
-<dd><p>names</p>
-</dd>
-</dl>\
-");
+#[test]
+fn simple_table() {
+    check_renders_to(
+        "\
+=====  =====
+A      B
+=====  =====
+1      2
+3
+=====  =====
+",
+        "\
+<table>
+<colgroup>
+<col style=\"width: 50%\">
+<col style=\"width: 50%\">
+</colgroup>
+<thead>
+<tr>
+<th><p>A</p></th>
+<th><p>B</p></th>
+</tr>
+</thead>
+<tbody>
+<tr>
+<td><p>1</p></td>
+<td><p>2</p></td>
+</tr>
+<tr>
+<td><p>3</p></td>
+<td></td>
+</tr>
+</tbody>
+</table>\
+",
+    );
 }
-*/
@@ -7,7 +7,7 @@ use std::io::Write;
 use anyhow::{Error, anyhow};
 use document_tree::Document;
 
-pub use crate::html::render_html;
+pub use crate::html::{HTMLOptions, MathOutput, render_html, render_html_with_options};
 pub use schemars::generate::SchemaSettings;
 
 /// Render a document tree as JSON.
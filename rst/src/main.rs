@@ -2,9 +2,10 @@
 
 use clap::Parser;
 
-use rst_parser::parse;
+use rst_parser::{parse, parse_with_path};
 use rst_renderer::{
-    SchemaSettings, render_html, render_json, render_json_schema_document, render_xml,
+    HTMLOptions, MathOutput, SchemaSettings, render_html_with_options, render_json,
+    render_json_schema_document, render_xml,
 };
 
 use std::io::{self, Read};
@@ -16,6 +17,14 @@ enum Format {
     Html,
 }
 
+#[derive(Debug, Clone, clap::ValueEnum)]
+enum MathFormat {
+    #[value(name = "mathml")]
+    MathML,
+    #[value(name = "mathjax")]
+    MathJax,
+}
+
 #[derive(Debug, Default, Clone, clap::ValueEnum)]
 enum SchemaVersion {
     // tooling is hopelessly outdated, draft 7 is kind of the best bet
@@ -37,6 +46,9 @@ struct Cli {
     format: Format,
     /// Input file
     file: Option<String>,
+    /// How to render math in HTML
+    #[arg(long, default_value = "mathml")]
+    math_output: MathFormat,
     #[command(flatten)]
     verbosity: clap_verbosity_flag::Verbosity,
     /// Print schema
@@ -70,11 +82,24 @@ fn main() -> Result<(), anyhow::Error> {
     }
 
     let content = preprocess_content(args.file.as_deref())?;
-    let document = parse(&content)?;
+    let document = match &args.file {
+        Some(file) => parse_with_path(&content, file.as_ref())?,
+        None => parse(&content)?,
+    };
     match args.format {
         Format::Json => render_json(&document, stdout)?,
         Format::Xml => render_xml(&document, stdout)?,
-        Format::Html => render_html(&document, stdout, true)?,
+        Format::Html => {
+            let math_output = match args.math_output {
+                MathFormat::MathML => MathOutput::MathML,
+                MathFormat::MathJax => MathOutput::MathJax,
+            };
+            let options = HTMLOptions {
+                standalone: true,
+                math_output,
+            };
+            render_html_with_options(&document, stdout, options)?;
+        }
     }
     Ok(())
 }